@@ -271,7 +271,9 @@ fn block_on_line(
             point![defense_line_x, defense_line_y_range.end],
         );
         let ball_target_line = Line(ball_position, target);
-        let intersection_point = defense_line.intersection(&ball_target_line);
+        let intersection_point = defense_line
+            .intersection(&ball_target_line)
+            .unwrap_or(ball_position);
         let defense_position = point![
             intersection_point.x(),
             intersection_point
@@ -12,11 +12,18 @@ use types::{
 pub fn execute(
     world_state: &WorldState,
     expected_referee_position: Option<Point2<Field>>,
+    should_wave_at_referee: bool,
 ) -> Option<MotionCommand> {
     if world_state.robot.primary_state != PrimaryState::Initial {
         return None;
     }
 
+    if should_wave_at_referee {
+        return Some(MotionCommand::Wave {
+            head: HeadMotion::Center,
+        });
+    }
+
     Some(
         look_at_referee(
             world_state.robot.ground_to_field,
@@ -22,6 +22,7 @@ use types::{
     planned_path::PathSegment,
     primary_state::PrimaryState,
     roles::Role,
+    sensor_data::SensorData,
     step_plan::Step,
     support_foot::Side,
     world_state::WorldState,
@@ -35,8 +36,9 @@ use super::{
     dribble, fall_safely,
     head::LookAction,
     initial, intercept_ball, jump, look_around, lost_ball, no_ground_contact, penalize,
-    prepare_jump, search, sit_down, stand, stand_up, support, unstiff, walk_to_kick_off,
-    walk_to_penalty_kick,
+    prepare_jump, search, sit_down, stand,
+    stand_up::{self, FallenDebounce},
+    support, unstiff, walk_to_kick_off, walk_to_penalty_kick,
     walk_to_pose::{WalkAndStand, WalkPathPlanner},
 };
 
@@ -46,6 +48,7 @@ pub struct Behavior {
     last_known_ball_position: Point2<Field>,
     active_since: Option<SystemTime>,
     previous_role: Role,
+    fallen_debounce: FallenDebounce,
 }
 
 #[context]
@@ -59,9 +62,13 @@ pub struct CycleContext {
 
     expected_referee_position: Input<Option<Point2<Field>>, "expected_referee_position?">,
     has_ground_contact: Input<bool, "has_ground_contact">,
+    should_wave_at_referee: Input<bool, "should_wave_at_referee">,
+    sensor_data: Input<SensorData, "sensor_data">,
     world_state: Input<WorldState, "world_state">,
     cycle_time: Input<CycleTime, "cycle_time">,
 
+    stand_up_front_aborted: CyclerState<bool, "stand_up_front_aborted">,
+
     parameters: Parameter<BehaviorParameters, "behavior">,
     in_walk_kicks: Parameter<InWalkKicksParameters, "in_walk_kicks">,
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
@@ -85,6 +92,7 @@ impl Behavior {
             last_known_ball_position: point![0.0, 0.0],
             active_since: None,
             previous_role: Role::Searcher,
+            fallen_debounce: FallenDebounce::default(),
         })
     }
 
@@ -115,6 +123,9 @@ impl Behavior {
             (Some(_), _) => self.active_since = None,
         }
 
+        self.fallen_debounce
+            .update(&context.world_state.robot.fall_state);
+
         if self.previous_role != context.world_state.robot.role
             && context.world_state.robot.role != Role::Searcher
             && context.world_state.robot.role != Role::Loser
@@ -244,13 +255,23 @@ impl Behavior {
                     Action::Unstiff => unstiff::execute(world_state),
                     Action::SitDown => sit_down::execute(world_state),
                     Action::Penalize => penalize::execute(world_state),
-                    Action::Initial => {
-                        initial::execute(world_state, context.expected_referee_position.cloned())
-                    }
+                    Action::Initial => initial::execute(
+                        world_state,
+                        context.expected_referee_position.cloned(),
+                        *context.should_wave_at_referee,
+                    ),
                     Action::FallSafely => {
                         fall_safely::execute(world_state, *context.has_ground_contact)
                     }
-                    Action::StandUp => stand_up::execute(world_state),
+                    Action::StandUp => stand_up::execute(
+                        world_state,
+                        context.sensor_data.inertial_measurement_unit.roll_pitch.y(),
+                        context.parameters.stand_up_facing_down_pitch_threshold,
+                        context.parameters.stand_up_pitch_override_margin,
+                        &self.fallen_debounce,
+                        context.parameters.stand_up_minimum_fallen_cycles,
+                        *context.stand_up_front_aborted,
+                    ),
                     Action::NoGroundContact => no_ground_contact::execute(world_state),
                     Action::LookAround => look_around::execute(world_state),
                     Action::InterceptBall => intercept_ball::execute(
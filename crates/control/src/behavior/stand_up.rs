@@ -1,9 +1,271 @@
-use types::{fall_state::FallState, motion_command::MotionCommand, world_state::WorldState};
+use serde::{Deserialize, Serialize};
+use types::{
+    fall_state::{FallState, Kind},
+    motion_command::MotionCommand,
+    world_state::WorldState,
+};
 
-pub fn execute(world_state: &WorldState) -> Option<MotionCommand> {
+/// Counts the consecutive cycles the robot has been fallen, so that a momentarily misdetected
+/// fall, e.g. during an aggressive kick, does not trigger a stand up.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct FallenDebounce {
+    fallen_cycles: usize,
+}
+
+impl FallenDebounce {
+    pub fn update(&mut self, fall_state: &FallState) {
+        self.fallen_cycles = match fall_state {
+            FallState::Fallen { .. } => self.fallen_cycles.saturating_add(1),
+            _ => 0,
+        };
+    }
+}
+
+pub fn execute(
+    world_state: &WorldState,
+    pitch: f32,
+    facing_down_pitch_threshold: f32,
+    pitch_override_margin: f32,
+    fallen_debounce: &FallenDebounce,
+    minimum_fallen_cycles: usize,
+    stand_up_aborted: bool,
+) -> Option<MotionCommand> {
+    // Releasing a motion given up by its watchdog lets it start over from the first frame.
+    if stand_up_aborted {
+        return None;
+    }
     match world_state.robot.fall_state {
-        FallState::Fallen { kind } => Some(MotionCommand::StandUp { kind }),
+        FallState::Fallen { .. } if fallen_debounce.fallen_cycles < minimum_fallen_cycles => None,
+        FallState::Fallen { kind } => Some(MotionCommand::StandUp {
+            kind: stand_up_kind(
+                kind,
+                pitch,
+                facing_down_pitch_threshold,
+                pitch_override_margin,
+            ),
+        }),
         FallState::StandingUp { kind, .. } => Some(MotionCommand::StandUp { kind }),
         _ => None,
     }
 }
+
+// A robot lying at an angle may be misclassified by the fall state estimation. The pitch only
+// overrides its decision if it is clearly on the other side of the threshold, so that pitch noise
+// around the threshold does not start the wrong stand up motion.
+fn stand_up_kind(
+    kind: Kind,
+    pitch: f32,
+    facing_down_pitch_threshold: f32,
+    pitch_override_margin: f32,
+) -> Kind {
+    match kind {
+        Kind::Sitting => Kind::Sitting,
+        Kind::FacingDown if pitch < facing_down_pitch_threshold - pitch_override_margin => {
+            Kind::FacingUp
+        }
+        Kind::FacingUp if pitch > facing_down_pitch_threshold + pitch_override_margin => {
+            Kind::FacingDown
+        }
+        kind => kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use types::world_state::RobotState;
+
+    use super::*;
+
+    const THRESHOLD: f32 = 0.0;
+    const MARGIN: f32 = 0.3;
+
+    fn world_state_with(fall_state: FallState) -> WorldState {
+        WorldState {
+            robot: RobotState {
+                fall_state,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn stand_up_kind_for(fall_state: FallState, pitch: f32) -> Option<Kind> {
+        let mut fallen_debounce = FallenDebounce::default();
+        fallen_debounce.update(&fall_state);
+        match execute(
+            &world_state_with(fall_state),
+            pitch,
+            THRESHOLD,
+            MARGIN,
+            &fallen_debounce,
+            1,
+            false,
+        ) {
+            Some(MotionCommand::StandUp { kind }) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn lying_on_front_stands_up_front() {
+        assert_eq!(
+            stand_up_kind_for(
+                FallState::Fallen {
+                    kind: Kind::FacingDown
+                },
+                FRAC_PI_2
+            ),
+            Some(Kind::FacingDown)
+        );
+    }
+
+    #[test]
+    fn lying_on_back_stands_up_back() {
+        assert_eq!(
+            stand_up_kind_for(
+                FallState::Fallen {
+                    kind: Kind::FacingUp
+                },
+                -FRAC_PI_2
+            ),
+            Some(Kind::FacingUp)
+        );
+    }
+
+    #[test]
+    fn pitch_overrides_the_fall_state_only_beyond_the_margin() {
+        let fallen_facing_up = FallState::Fallen {
+            kind: Kind::FacingUp,
+        };
+        assert_eq!(
+            stand_up_kind_for(fallen_facing_up, THRESHOLD + 0.01),
+            Some(Kind::FacingUp)
+        );
+        assert_eq!(
+            stand_up_kind_for(fallen_facing_up, THRESHOLD + MARGIN + 0.01),
+            Some(Kind::FacingDown)
+        );
+
+        let fallen_facing_down = FallState::Fallen {
+            kind: Kind::FacingDown,
+        };
+        assert_eq!(
+            stand_up_kind_for(fallen_facing_down, THRESHOLD - MARGIN - 0.01),
+            Some(Kind::FacingUp)
+        );
+    }
+
+    #[test]
+    fn pitch_noise_below_the_threshold_keeps_facing_down() {
+        assert_eq!(
+            stand_up_kind_for(
+                FallState::Fallen {
+                    kind: Kind::FacingDown
+                },
+                THRESHOLD - 0.01
+            ),
+            Some(Kind::FacingDown)
+        );
+    }
+
+    #[test]
+    fn sitting_and_ongoing_stand_up_keep_their_kind() {
+        assert_eq!(
+            stand_up_kind_for(
+                FallState::Fallen {
+                    kind: Kind::Sitting
+                },
+                FRAC_PI_2
+            ),
+            Some(Kind::Sitting)
+        );
+        assert_eq!(
+            stand_up_kind_for(
+                FallState::StandingUp {
+                    start_time: std::time::UNIX_EPOCH,
+                    kind: Kind::FacingUp
+                },
+                FRAC_PI_2
+            ),
+            Some(Kind::FacingUp)
+        );
+        assert_eq!(stand_up_kind_for(FallState::Upright, FRAC_PI_2), None);
+    }
+
+    fn stands_up_in_cycle(fall_states: &[FallState]) -> Option<usize> {
+        let mut fallen_debounce = FallenDebounce::default();
+        fall_states.iter().position(|fall_state| {
+            fallen_debounce.update(fall_state);
+            execute(
+                &world_state_with(*fall_state),
+                0.0,
+                THRESHOLD,
+                MARGIN,
+                &fallen_debounce,
+                3,
+                false,
+            )
+            .is_some()
+        })
+    }
+
+    #[test]
+    fn aborted_stand_up_is_released() {
+        let standing_up = FallState::StandingUp {
+            start_time: std::time::UNIX_EPOCH,
+            kind: Kind::FacingDown,
+        };
+        let fallen_debounce = FallenDebounce::default();
+
+        assert!(execute(
+            &world_state_with(standing_up),
+            FRAC_PI_2,
+            THRESHOLD,
+            MARGIN,
+            &fallen_debounce,
+            1,
+            true,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn flickering_fall_detection_does_not_stand_up() {
+        let fallen = FallState::Fallen {
+            kind: Kind::FacingDown,
+        };
+        let flickering = [
+            FallState::Upright,
+            fallen,
+            fallen,
+            FallState::Upright,
+            fallen,
+            FallState::Upright,
+            fallen,
+            fallen,
+            FallState::Upright,
+        ];
+
+        assert_eq!(stands_up_in_cycle(&flickering), None);
+    }
+
+    #[test]
+    fn persistent_fall_stands_up_after_minimum_cycles() {
+        let fallen = FallState::Fallen {
+            kind: Kind::FacingUp,
+        };
+        let genuine_fall = [
+            FallState::Upright,
+            fallen,
+            FallState::Upright,
+            fallen,
+            fallen,
+            fallen,
+            fallen,
+        ];
+
+        assert_eq!(stands_up_in_cycle(&genuine_fall), Some(5));
+    }
+}
@@ -61,6 +61,8 @@ pub struct CycleContext {
     sensor_data: Input<SensorData, "sensor_data">,
     cycle_time: Input<CycleTime, "cycle_time">,
     has_ground_contact: Input<bool, "has_ground_contact">,
+
+    stand_up_front_aborted: CyclerState<bool, "stand_up_front_aborted">,
 }
 
 #[context]
@@ -146,13 +148,7 @@ impl FallStateEstimation {
                 ..context.falling_angle_threshold_forward.y())
                 .contains(&estimated_pitch)
             {
-                let side = {
-                    if estimated_roll > 0.0 {
-                        Side::Right
-                    } else {
-                        Side::Left
-                    }
-                };
+                let side = Side::from_roll(estimated_roll);
                 if estimated_pitch > 0.0 {
                     Some(Direction::Forward { side })
                 } else {
@@ -213,6 +209,9 @@ impl FallStateEstimation {
             },
             (FallState::StandingUp { .. }, None, None) => FallState::Upright,
             (current @ FallState::StandingUp { .. }, Some(..), None) => current,
+            (FallState::StandingUp { .. }, _, Some(facing)) if *context.stand_up_front_aborted => {
+                FallState::Fallen { kind: facing }
+            }
             (current @ FallState::StandingUp { start_time, .. }, _, Some(facing)) => {
                 if cycle_start.duration_since(start_time).unwrap() > *context.falling_timeout {
                     FallState::Fallen { kind: (facing) }
@@ -8,15 +8,21 @@ use linear_algebra::{distance, Isometry2, Point2, Vector2};
 use serde::{Deserialize, Serialize};
 use spl_network_messages::{GamePhase, GameState, Team};
 use types::{
-    ball_position::BallPosition, cycle_time::CycleTime, field_dimensions::FieldDimensions,
+    ball_position::BallPosition,
+    cycle_time::CycleTime,
+    field_dimensions::FieldDimensions,
     filtered_game_controller_state::FilteredGameControllerState,
-    filtered_game_state::FilteredGameState, filtered_whistle::FilteredWhistle,
-    game_controller_state::GameControllerState, parameters::GameStateFilterParameters,
+    filtered_game_state::FilteredGameState,
+    filtered_whistle::FilteredWhistle,
+    game_controller_state::GameControllerState,
+    motion_selection::{MotionSafeExits, MotionType},
+    parameters::GameStateFilterParameters,
 };
 #[derive(Deserialize, Serialize)]
 pub struct GameControllerStateFilter {
     state: State,
     opponent_state: State,
+    is_waving_at_referee: bool,
 }
 
 #[context]
@@ -31,13 +37,16 @@ pub struct CycleContext {
     game_controller_state: RequiredInput<Option<GameControllerState>, "game_controller_state?">,
     config: Parameter<GameStateFilterParameters, "game_state_filter">,
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    wave_at_referee: Parameter<bool, "behavior.wave_at_referee">,
 
     ground_to_field: CyclerState<Isometry2<Ground, Field>, "ground_to_field">,
+    motion_safe_exits: CyclerState<MotionSafeExits, "motion_safe_exits">,
 }
 
 #[context]
 pub struct MainOutputs {
     pub filtered_game_controller_state: MainOutput<Option<FilteredGameControllerState>>,
+    pub should_wave_at_referee: MainOutput<bool>,
 }
 
 impl GameControllerStateFilter {
@@ -45,10 +54,23 @@ impl GameControllerStateFilter {
         Ok(Self {
             state: State::Initial,
             opponent_state: State::Initial,
+            is_waving_at_referee: false,
         })
     }
 
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        // With waving enabled, a detected referee pose is only accepted as ready signal once the
+        // wave has been played to its end, otherwise leaving Initial would cut the wave short.
+        self.is_waving_at_referee = *context.wave_at_referee
+            && matches!(self.state, State::Initial)
+            && context.game_controller_state.game_state == GameState::Initial
+            && (self.is_waving_at_referee || *context.is_referee_initial_pose_detected);
+        let is_referee_initial_pose_detected = if *context.wave_at_referee {
+            self.is_waving_at_referee && context.motion_safe_exits[MotionType::Wave]
+        } else {
+            *context.is_referee_initial_pose_detected
+        };
+
         let game_states = filter_game_states(
             *context.ground_to_field,
             context.ball_position,
@@ -59,7 +81,7 @@ impl GameControllerStateFilter {
             context.cycle_time,
             &mut self.state,
             &mut self.opponent_state,
-            *context.is_referee_initial_pose_detected,
+            is_referee_initial_pose_detected,
         );
         let filtered_game_controller_state = FilteredGameControllerState {
             game_state: game_states.own,
@@ -78,6 +100,7 @@ impl GameControllerStateFilter {
 
         Ok(MainOutputs {
             filtered_game_controller_state: Some(filtered_game_controller_state).into(),
+            should_wave_at_referee: self.is_waving_at_referee.into(),
         })
     }
 }
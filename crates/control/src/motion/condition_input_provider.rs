@@ -4,7 +4,10 @@ use filtering::low_pass_filter::LowPassFilter;
 use framework::MainOutput;
 use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
-use types::{condition_input::ConditionInput, fall_state::FallState, sensor_data::SensorData};
+use types::{
+    condition_input::ConditionInput, cycle_time::CycleTime, fall_state::FallState,
+    primary_state::PrimaryState, sensor_data::SensorData,
+};
 
 #[derive(Default, Deserialize, Serialize)]
 pub struct ConditionInputProvider {
@@ -20,6 +23,8 @@ pub struct CreationContext {
 pub struct CycleContext {
     sensor_data: Input<SensorData, "sensor_data">,
     fall_state: Input<FallState, "fall_state">,
+    cycle_time: Input<CycleTime, "cycle_time">,
+    primary_state: Input<PrimaryState, "primary_state">,
 }
 
 #[context]
@@ -39,17 +44,26 @@ impl ConditionInputProvider {
     }
 
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
-        self.angular_velocity_filter.update(
-            context
-                .sensor_data
-                .inertial_measurement_unit
-                .angular_velocity
-                .inner,
-        );
+        let angular_velocity = context
+            .sensor_data
+            .inertial_measurement_unit
+            .angular_velocity
+            .inner;
+        self.angular_velocity_filter.update(angular_velocity);
         Ok(MainOutputs {
             condition_input: ConditionInput {
+                angular_velocity,
                 filtered_angular_velocity: self.angular_velocity_filter.state(),
+                linear_acceleration: context
+                    .sensor_data
+                    .inertial_measurement_unit
+                    .linear_acceleration
+                    .inner,
                 fall_state: *context.fall_state,
+                cycle_time: *context.cycle_time,
+                primary_state: *context.primary_state,
+                positions: context.sensor_data.positions,
+                force_sensitive_resistors: context.sensor_data.force_sensitive_resistors.clone(),
             }
             .into(),
         })
@@ -35,6 +35,7 @@ pub struct CycleContext {
     stand_up_front_positions: Input<Joints<f32>, "stand_up_front_positions">,
     stand_up_sitting_positions: Input<Joints<f32>, "stand_up_sitting_positions">,
     walk_motor_commands: Input<MotorCommands<BodyJoints<f32>>, "walk_motor_commands">,
+    wave_joints_command: Input<MotorCommands<Joints<f32>>, "wave_joints_command">,
 
     initial_pose: Parameter<Joints<f32>, "initial_pose">,
     penalized_pose: Parameter<Joints<f32>, "penalized_pose">,
@@ -63,7 +64,9 @@ impl DispatchingInterpolator {
     }
 
     pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
-        context.motion_safe_exits[MotionType::Dispatching] = false;
+        context
+            .motion_safe_exits
+            .set(MotionType::Dispatching, false);
 
         let dispatching = context.motion_selection.current_motion == MotionType::Dispatching;
         if !dispatching {
@@ -103,6 +106,7 @@ impl DispatchingInterpolator {
                     HeadJoints::fill(0.0),
                     context.walk_motor_commands.positions,
                 ),
+                MotionType::Wave => context.wave_joints_command.positions,
             };
 
             self.interpolator = TimedSpline::try_new_transition_timed(
@@ -117,7 +121,9 @@ impl DispatchingInterpolator {
         self.interpolator
             .advance_by(context.cycle_time.last_cycle_duration);
 
-        context.motion_safe_exits[MotionType::Dispatching] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::Dispatching, self.interpolator.is_finished());
         context.transition_time.fill_if_subscribed(|| {
             if self.interpolator.is_finished() {
                 None
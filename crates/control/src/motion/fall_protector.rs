@@ -64,7 +64,9 @@ impl FallProtector {
 
         let (start_time, falling_direction) = match *context.fall_state {
             FallState::Upright { .. } | FallState::Fallen { .. } | FallState::StandingUp { .. } => {
-                context.motion_safe_exits[MotionType::FallProtection] = true;
+                context
+                    .motion_safe_exits
+                    .set(MotionType::FallProtection, true);
                 return Ok(MainOutputs::default());
             }
             FallState::Falling {
@@ -72,7 +74,9 @@ impl FallProtector {
                 direction,
             } => (start_time, direction),
         };
-        context.motion_safe_exits[MotionType::FallProtection] = false;
+        context
+            .motion_safe_exits
+            .set(MotionType::FallProtection, false);
 
         let phase = if context
             .cycle_time
@@ -55,7 +55,9 @@ impl JumpLeft {
             self.interpolator.reset();
         }
 
-        context.motion_safe_exits[MotionType::JumpLeft] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::JumpLeft, self.interpolator.is_finished());
 
         Ok(MainOutputs {
             jump_left_joints_command: self.interpolator.value().into(),
@@ -55,7 +55,9 @@ impl JumpRight {
             self.interpolator.reset();
         }
 
-        context.motion_safe_exits[MotionType::JumpRight] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::JumpRight, self.interpolator.is_finished());
 
         Ok(MainOutputs {
             jump_right_joints_command: self.interpolator.value().mirrored().into(),
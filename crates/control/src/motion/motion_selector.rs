@@ -88,6 +88,7 @@ fn motion_type_from_command(command: &MotionCommand) -> MotionType {
         MotionCommand::Unstiff => MotionType::Unstiff,
         MotionCommand::Walk { .. } => MotionType::Walk,
         MotionCommand::InWalkKick { .. } => MotionType::Walk,
+        MotionCommand::Wave { .. } => MotionType::Wave,
     }
 }
 
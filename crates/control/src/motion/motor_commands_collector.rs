@@ -32,8 +32,10 @@ pub struct CycleContext {
     sit_down_joints_command: Input<MotorCommands<Joints<f32>>, "sit_down_joints_command">,
     stand_up_back_positions: Input<Joints<f32>, "stand_up_back_positions">,
     stand_up_front_positions: Input<Joints<f32>, "stand_up_front_positions">,
+    stand_up_front_relaxed: Input<bool, "stand_up_front_relaxed">,
     stand_up_sitting_positions: Input<Joints<f32>, "stand_up_sitting_positions">,
     walk_motor_commands: Input<MotorCommands<BodyJoints<f32>>, "walk_motor_commands">,
+    wave_joints_command: Input<MotorCommands<Joints<f32>>, "wave_joints_command">,
     cycle_time: Input<CycleTime, "cycle_time">,
 
     joint_calibration_offsets: Parameter<Joints<f32>, "joint_calibration_offsets">,
@@ -75,6 +77,7 @@ impl MotorCommandCollector {
         let stand_up_front_positions = context.stand_up_front_positions;
         let stand_up_sitting_positions = context.stand_up_sitting_positions;
         let walk = context.walk_motor_commands;
+        let wave = context.wave_joints_command;
 
         let (positions, stiffnesses) = match motion_selection.current_motion {
             MotionType::ArmsUpSquat => (arms_up_squat.positions, arms_up_squat.stiffnesses),
@@ -131,6 +134,9 @@ impl MotorCommandCollector {
                     },
                 ),
             ),
+            MotionType::StandUpFront if *context.stand_up_front_relaxed => {
+                (*stand_up_front_positions, Joints::fill(0.0))
+            }
             MotionType::StandUpFront => (
                 *stand_up_front_positions,
                 Joints::from_head_and_body(
@@ -160,6 +166,13 @@ impl MotorCommandCollector {
                 Joints::from_head_and_body(head_joints_command.positions, walk.positions),
                 Joints::from_head_and_body(head_joints_command.stiffnesses, walk.stiffnesses),
             ),
+            MotionType::Wave => (
+                Joints::from_head_and_body(head_joints_command.positions, wave.positions.body()),
+                Joints::from_head_and_body(
+                    head_joints_command.stiffnesses,
+                    wave.stiffnesses.body(),
+                ),
+            ),
         };
 
         // The actuators use the raw sensor data (not corrected like current_positions) in their feedback loops,
@@ -55,7 +55,9 @@ impl SitDown {
             self.interpolator.reset();
         }
 
-        context.motion_safe_exits[MotionType::SitDown] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::SitDown, self.interpolator.is_finished());
 
         Ok(MainOutputs {
             sit_down_joints_command: MotorCommands {
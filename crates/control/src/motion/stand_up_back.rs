@@ -4,23 +4,26 @@ use color_eyre::Result;
 use context_attribute::context;
 use framework::MainOutput;
 use hardware::PathsInterface;
-use motionfile::{MotionFile, MotionInterpolator};
+use motionfile::{ExcessiveAcceleration, MirroredInterpolator, MotionFile};
 use serde::{Deserialize, Serialize};
 use types::{
     condition_input::ConditionInput,
     cycle_time::CycleTime,
     joints::Joints,
     motion_selection::{MotionSafeExits, MotionSelection, MotionType},
+    sensor_data::SensorData,
 };
 
 #[derive(Deserialize, Serialize)]
 pub struct StandUpBack {
-    interpolator: MotionInterpolator<Joints<f32>>,
+    interpolator: MirroredInterpolator<Joints<f32>>,
 }
 
 #[context]
 pub struct CreationContext {
     hardware_interface: HardwareInterface,
+
+    excessive_acceleration_threshold: Parameter<f32, "stand_up.excessive_acceleration_threshold">,
 }
 
 #[context]
@@ -28,6 +31,7 @@ pub struct CycleContext {
     condition_input: Input<ConditionInput, "condition_input">,
     cycle_time: Input<CycleTime, "cycle_time">,
     motion_selection: Input<MotionSelection, "motion_selection">,
+    sensor_data: Input<SensorData, "sensor_data">,
 
     motion_safe_exits: CyclerState<MotionSafeExits, "motion_safe_exits">,
 }
@@ -42,35 +46,43 @@ pub struct MainOutputs {
 impl StandUpBack {
     pub fn new(context: CreationContext<impl PathsInterface>) -> Result<Self> {
         let paths = context.hardware_interface.get_paths();
-        Ok(Self {
-            interpolator: MotionFile::from_path(paths.motions.join("stand_up_back.json"))?
-                .try_into()?,
-        })
+        let motion_file = MotionFile::from_path(paths.motions.join("stand_up_back.json"))?;
+        let mut interpolator = MirroredInterpolator::try_from(motion_file)?;
+        interpolator.add_interrupt_condition(
+            ExcessiveAcceleration::new(*context.excessive_acceleration_threshold).into(),
+        );
+        Ok(Self { interpolator })
     }
 
     pub fn advance_interpolator(&mut self, context: CycleContext) {
         let last_cycle_duration = context.cycle_time.last_cycle_duration;
         let condition_input = context.condition_input;
 
-        context.motion_safe_exits[MotionType::StandUpBack] = false;
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpBack, false);
 
-        self.interpolator
-            .advance_by(last_cycle_duration, condition_input);
+        let interpolator = self.interpolator.active_mut();
+        interpolator.advance_by(last_cycle_duration, condition_input);
 
-        context.motion_safe_exits[MotionType::StandUpBack] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpBack, interpolator.is_finished());
     }
 
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
         let stand_up_back_estimated_remaining_duration =
             if let MotionType::StandUpBack = context.motion_selection.current_motion {
+                self.interpolator
+                    .select_side(context.sensor_data.inertial_measurement_unit.roll_pitch.x());
                 self.advance_interpolator(context);
-                Some(self.interpolator.estimated_remaining_duration())
+                Some(self.interpolator.active().estimated_remaining_duration())
             } else {
                 self.interpolator.reset();
                 None
             };
         Ok(MainOutputs {
-            stand_up_back_positions: self.interpolator.value().into(),
+            stand_up_back_positions: self.interpolator.active().value().into(),
             stand_up_back_estimated_remaining_duration: stand_up_back_estimated_remaining_duration
                 .into(),
         })
@@ -2,25 +2,32 @@ use std::time::Duration;
 
 use color_eyre::Result;
 use context_attribute::context;
-use framework::MainOutput;
+use framework::{AdditionalOutput, MainOutput};
 use hardware::PathsInterface;
-use motionfile::{MotionFile, MotionInterpolator};
+use log::warn;
+use motionfile::{ConditionEvaluation, ExcessiveAcceleration, MirroredInterpolator, MotionFile};
 use serde::{Deserialize, Serialize};
 use types::{
     condition_input::ConditionInput,
     cycle_time::CycleTime,
     joints::Joints,
     motion_selection::{MotionSafeExits, MotionSelection, MotionType},
+    sensor_data::SensorData,
 };
 
 #[derive(Deserialize, Serialize)]
 pub struct StandUpFront {
-    interpolator: MotionInterpolator<Joints<f32>>,
+    /// The side is chosen anew when resuming after an interruption.
+    interpolator: MirroredInterpolator<Joints<f32>>,
+    time_in_motion: Duration,
+    interrupted: bool,
 }
 
 #[context]
 pub struct CreationContext {
     hardware_interface: HardwareInterface,
+
+    excessive_acceleration_threshold: Parameter<f32, "stand_up.excessive_acceleration_threshold">,
 }
 
 #[context]
@@ -28,51 +35,333 @@ pub struct CycleContext {
     condition_input: Input<ConditionInput, "condition_input">,
     cycle_time: Input<CycleTime, "cycle_time">,
     motion_selection: Input<MotionSelection, "motion_selection">,
+    sensor_data: Input<SensorData, "sensor_data">,
+
+    watchdog_timeout: Parameter<Duration, "stand_up.watchdog_timeout">,
+    interruption_blend_duration: Parameter<Duration, "stand_up.interruption_blend_duration">,
+    relax_on_abort: Parameter<bool, "stand_up.relax_on_abort">,
 
     motion_safe_exits: CyclerState<MotionSafeExits, "motion_safe_exits">,
+    /// Whether the watchdog gave up the motion, so that behavior and fall state estimation stop
+    /// waiting for it to finish.
+    stand_up_front_aborted: CyclerState<bool, "stand_up_front_aborted">,
+
+    stand_up_front_progress: AdditionalOutput<f32, "stand_up_front_progress">,
+    stand_up_front_condition_evaluation:
+        AdditionalOutput<Option<ConditionEvaluation>, "stand_up_front_condition_evaluation">,
 }
 
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
     pub stand_up_front_positions: MainOutput<Joints<f32>>,
+    /// Whether the motion aborted and all joints are relaxed at the measured positions instead
+    /// of holding the aborted frame.
+    pub stand_up_front_relaxed: MainOutput<bool>,
     pub stand_up_front_estimated_remaining_duration: MainOutput<Option<Duration>>,
 }
 
 impl StandUpFront {
     pub fn new(context: CreationContext<impl PathsInterface>) -> Result<Self> {
         let paths = context.hardware_interface.get_paths();
+        Self::from_motion_file(
+            MotionFile::from_path(paths.motions.join("stand_up_front.json"))?,
+            *context.excessive_acceleration_threshold,
+        )
+    }
+
+    fn from_motion_file(
+        motion_file: MotionFile<Joints<f32>>,
+        excessive_acceleration_threshold: f32,
+    ) -> Result<Self> {
+        let mut interpolator = MirroredInterpolator::try_from(motion_file)?;
+        interpolator.add_interrupt_condition(
+            ExcessiveAcceleration::new(excessive_acceleration_threshold).into(),
+        );
         Ok(Self {
-            interpolator: MotionFile::from_path(paths.motions.join("stand_up_front.json"))?
-                .try_into()?,
+            interpolator,
+            time_in_motion: Duration::ZERO,
+            interrupted: false,
         })
     }
 
-    pub fn advance_interpolator(&mut self, context: CycleContext) {
+    /// Restarts the motion from the pose it was interrupted at instead of snapping to the first
+    /// frame. The robot may have rolled over meanwhile, so the side is chosen again.
+    fn resume_after_interruption(&mut self, roll: f32, blend_duration: Duration) -> Result<()> {
+        let current = self.interpolator.active().value();
+        self.interpolator.reset();
+        self.interpolator.reselect_side(roll);
+        self.interpolator
+            .active_mut()
+            .start_from_current(current, blend_duration)?;
+        self.interrupted = false;
+        Ok(())
+    }
+
+    /// Keeps an interrupted motion to be resumed on re-selection. A finished motion or one given up
+    /// by the watchdog starts over from the first frame instead.
+    fn deselect(&mut self, watchdog_timeout: Duration) {
+        if self.watchdog_expired(watchdog_timeout) || self.interpolator.active().is_finished() {
+            self.interpolator.reset();
+            self.interrupted = false;
+        } else if self.time_in_motion > Duration::ZERO {
+            self.interrupted = true;
+        }
+        self.time_in_motion = Duration::ZERO;
+    }
+
+    fn watchdog_expired(&self, watchdog_timeout: Duration) -> bool {
+        self.time_in_motion > watchdog_timeout
+    }
+
+    /// Nothing remains of a motion given up by the watchdog, even though its interpolator stopped
+    /// before the end.
+    fn estimated_remaining_duration(&self, watchdog_timeout: Duration) -> Duration {
+        if self.watchdog_expired(watchdog_timeout) {
+            Duration::ZERO
+        } else {
+            self.interpolator.active().estimated_remaining_duration()
+        }
+    }
+
+    /// Positions to command and whether to relax all joints. With `relax_on_abort`, an aborted
+    /// motion relaxes at the measured positions, so the servos do not strain against the ground.
+    fn output_positions(
+        &self,
+        relax_on_abort: bool,
+        measured_positions: Joints<f32>,
+    ) -> (Joints<f32>, bool) {
+        let interpolator = self.interpolator.active();
+        if relax_on_abort && interpolator.abort_reason().is_some() {
+            return (measured_positions, true);
+        }
+        (interpolator.value(), false)
+    }
+
+    pub fn advance_interpolator(&mut self, mut context: CycleContext) {
         let last_cycle_duration = context.cycle_time.last_cycle_duration;
         let condition_input = context.condition_input;
 
-        context.motion_safe_exits[MotionType::StandUpFront] = false;
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpFront, false);
 
-        self.interpolator
-            .advance_by(last_cycle_duration, condition_input);
+        let watchdog_already_expired = self.watchdog_expired(*context.watchdog_timeout);
+        self.time_in_motion += last_cycle_duration;
+        let watchdog_expired = self.watchdog_expired(*context.watchdog_timeout);
+        *context.stand_up_front_aborted = watchdog_expired;
+        let interpolator = self.interpolator.active_mut();
+        if watchdog_expired {
+            if !watchdog_already_expired {
+                warn!(
+                    "stand up front exceeded watchdog timeout of {:?}, waiting on {:?}",
+                    context.watchdog_timeout,
+                    interpolator.waiting_condition()
+                );
+            }
+            context
+                .motion_safe_exits
+                .set(MotionType::StandUpFront, true);
+            return;
+        }
+
+        interpolator.advance_by(last_cycle_duration, condition_input);
 
-        context.motion_safe_exits[MotionType::StandUpFront] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpFront, interpolator.is_finished());
+        context
+            .stand_up_front_progress
+            .fill_if_subscribed(|| interpolator.progress());
+        context
+            .stand_up_front_condition_evaluation
+            .fill_if_subscribed(|| interpolator.last_condition_evaluation());
     }
 
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        let watchdog_timeout = *context.watchdog_timeout;
+        let relax_on_abort = *context.relax_on_abort;
+        let measured_positions = context.sensor_data.positions;
         let stand_up_front_estimated_remaining_duration =
             if let MotionType::StandUpFront = context.motion_selection.current_motion {
+                let roll = context.sensor_data.inertial_measurement_unit.roll_pitch.x();
+                if self.interrupted {
+                    self.resume_after_interruption(roll, *context.interruption_blend_duration)?;
+                } else {
+                    self.interpolator.select_side(roll);
+                }
                 self.advance_interpolator(context);
-                Some(self.interpolator.estimated_remaining_duration())
+                Some(self.estimated_remaining_duration(watchdog_timeout))
             } else {
-                self.interpolator.reset();
+                *context.stand_up_front_aborted = false;
+                self.deselect(watchdog_timeout);
                 None
             };
+        let (positions, relaxed) = self.output_positions(relax_on_abort, measured_positions);
         Ok(MainOutputs {
-            stand_up_front_positions: self.interpolator.value().into(),
+            stand_up_front_positions: positions.into(),
+            stand_up_front_relaxed: relaxed.into(),
             stand_up_front_estimated_remaining_duration:
                 stand_up_front_estimated_remaining_duration.into(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use motionfile::{KeyFrame, MotionFileFrame, CURRENT_MOTION_FILE_VERSION};
+    use types::{fall_state::Side, joints::mirror::Mirror};
+
+    use super::*;
+
+    fn stand_up_front() -> StandUpFront {
+        let mut initial_positions = Joints::default();
+        initial_positions.left_arm.shoulder_roll = 0.5;
+        initial_positions.right_leg.hip_roll = -0.2;
+        StandUpFront::from_motion_file(
+            MotionFile {
+                version: CURRENT_MOTION_FILE_VERSION,
+                interpolation_mode: Default::default(),
+                initial_positions,
+                motion: vec![MotionFileFrame {
+                    name: None,
+                    entry_condition: None,
+                    interrupt_conditions: vec![],
+                    keyframes: vec![KeyFrame {
+                        duration: Duration::from_secs(1),
+                        positions: Joints::default(),
+                    }],
+                    exit_condition: None,
+                }],
+            },
+            30.0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn variant_is_chosen_by_the_side_the_robot_lies_on() {
+        let mut stand_up = stand_up_front();
+        let original = stand_up.interpolator.original().value();
+
+        stand_up.interpolator.select_side(0.3);
+        assert_eq!(stand_up.interpolator.side(), Some(Side::Right));
+        assert_eq!(stand_up.interpolator.active().value(), original);
+
+        stand_up.interpolator.reset();
+        stand_up.interpolator.select_side(-0.3);
+        assert_eq!(stand_up.interpolator.side(), Some(Side::Left));
+        assert_eq!(stand_up.interpolator.active().value(), original.mirrored());
+
+        stand_up.interpolator.select_side(0.3);
+        assert_eq!(stand_up.interpolator.side(), Some(Side::Left));
+    }
+
+    #[test]
+    fn side_is_chosen_again_when_resuming_after_an_interruption() {
+        let mut stand_up = stand_up_front();
+        stand_up.interpolator.select_side(0.3);
+        stand_up
+            .interpolator
+            .active_mut()
+            .advance_by(Duration::from_millis(500), &ConditionInput::default());
+        let interrupted_positions = stand_up.interpolator.active().value();
+        stand_up.interrupted = true;
+
+        stand_up
+            .resume_after_interruption(-0.3, Duration::from_millis(200))
+            .unwrap();
+
+        assert_eq!(stand_up.interpolator.side(), Some(Side::Left));
+        assert!(!stand_up.interrupted);
+        assert_eq!(
+            stand_up.interpolator.active().value(),
+            interrupted_positions
+        );
+        assert_eq!(
+            stand_up.interpolator.original().value(),
+            stand_up_front().interpolator.original().value()
+        );
+    }
+
+    #[test]
+    fn motion_starts_over_after_the_watchdog_expired() {
+        let mut stand_up = stand_up_front();
+        let watchdog_timeout = Duration::from_secs(2);
+        stand_up.interpolator.select_side(-0.3);
+        stand_up
+            .interpolator
+            .active_mut()
+            .advance_by(Duration::from_millis(500), &ConditionInput::default());
+        stand_up.time_in_motion = Duration::from_secs(3);
+
+        stand_up.deselect(watchdog_timeout);
+
+        assert_eq!(stand_up.interpolator.side(), None);
+        assert!(!stand_up.interrupted);
+        assert_eq!(stand_up.time_in_motion, Duration::ZERO);
+        assert_eq!(
+            stand_up.interpolator.mirrored().value(),
+            stand_up_front().interpolator.mirrored().value()
+        );
+
+        stand_up.interpolator.select_side(0.3);
+        assert_eq!(stand_up.interpolator.side(), Some(Side::Right));
+        assert_eq!(
+            stand_up.interpolator.active().value(),
+            stand_up_front().interpolator.original().value()
+        );
+    }
+
+    #[test]
+    fn nothing_remains_after_the_watchdog_expired() {
+        let mut stand_up = stand_up_front();
+        let watchdog_timeout = Duration::from_secs(2);
+        stand_up.interpolator.select_side(0.3);
+        stand_up
+            .interpolator
+            .active_mut()
+            .advance_by(Duration::from_millis(500), &ConditionInput::default());
+        stand_up.time_in_motion = Duration::from_millis(1500);
+        assert!(stand_up.estimated_remaining_duration(watchdog_timeout) > Duration::ZERO);
+        assert!(!stand_up.watchdog_expired(watchdog_timeout));
+
+        stand_up.time_in_motion = Duration::from_secs(3);
+
+        assert_eq!(
+            stand_up.estimated_remaining_duration(watchdog_timeout),
+            Duration::ZERO
+        );
+        assert!(stand_up.watchdog_expired(watchdog_timeout));
+    }
+
+    #[test]
+    fn joints_are_relaxed_after_an_abort_if_enabled() {
+        let mut stand_up = stand_up_front();
+        let mut measured_positions = Joints::default();
+        measured_positions.left_leg.knee_pitch = 1.2;
+        stand_up.interpolator.select_side(0.3);
+        let held_positions = stand_up.interpolator.original().value();
+        assert_eq!(
+            stand_up.output_positions(true, measured_positions),
+            (held_positions, false)
+        );
+
+        let mut condition_input = ConditionInput::default();
+        condition_input.linear_acceleration.z = 100.0;
+        stand_up
+            .interpolator
+            .active_mut()
+            .advance_by(Duration::from_millis(10), &condition_input);
+        assert!(stand_up.interpolator.active().abort_reason().is_some());
+
+        assert_eq!(
+            stand_up.output_positions(true, measured_positions),
+            (measured_positions, true)
+        );
+        assert_eq!(
+            stand_up.output_positions(false, measured_positions),
+            (held_positions, false)
+        );
+    }
+}
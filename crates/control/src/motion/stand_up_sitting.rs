@@ -4,7 +4,7 @@ use color_eyre::Result;
 use context_attribute::context;
 use framework::MainOutput;
 use hardware::PathsInterface;
-use motionfile::{MotionFile, MotionInterpolator};
+use motionfile::{ExcessiveAcceleration, MotionFile, MotionInterpolator};
 use serde::{Deserialize, Serialize};
 use types::{
     condition_input::ConditionInput,
@@ -21,6 +21,8 @@ pub struct StandUpSitting {
 #[context]
 pub struct CreationContext {
     hardware_interface: HardwareInterface,
+
+    excessive_acceleration_threshold: Parameter<f32, "stand_up.excessive_acceleration_threshold">,
 }
 
 #[context]
@@ -42,22 +44,28 @@ pub struct MainOutputs {
 impl StandUpSitting {
     pub fn new(context: CreationContext<impl PathsInterface>) -> Result<Self> {
         let paths = context.hardware_interface.get_paths();
-        Ok(Self {
-            interpolator: MotionFile::from_path(paths.motions.join("stand_up_sitting.json"))?
-                .try_into()?,
-        })
+        let mut interpolator: MotionInterpolator<Joints<f32>> =
+            MotionFile::from_path(paths.motions.join("stand_up_sitting.json"))?.try_into()?;
+        interpolator.add_interrupt_condition(
+            ExcessiveAcceleration::new(*context.excessive_acceleration_threshold).into(),
+        );
+        Ok(Self { interpolator })
     }
 
     pub fn advance_interpolator(&mut self, context: CycleContext) {
         let last_cycle_duration = context.cycle_time.last_cycle_duration;
         let condition_input = context.condition_input;
 
-        context.motion_safe_exits[MotionType::StandUpSitting] = false;
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpSitting, false);
 
         self.interpolator
             .advance_by(last_cycle_duration, condition_input);
 
-        context.motion_safe_exits[MotionType::StandUpSitting] = self.interpolator.is_finished();
+        context
+            .motion_safe_exits
+            .set(MotionType::StandUpSitting, self.interpolator.is_finished());
     }
 
     pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
@@ -137,7 +137,9 @@ impl WalkingEngine {
         *cycle_context.walk_return_offset = self
             .calculate_return_offset(cycle_context.parameters, robot_to_walk)
             .unwrap_or_default();
-        cycle_context.motion_safe_exits[MotionType::Walk] = self.engine.is_standing();
+        cycle_context
+            .motion_safe_exits
+            .set(MotionType::Walk, self.engine.is_standing());
 
         cycle_context
             .debug_output
@@ -18,7 +18,59 @@ pub struct Line<Frame, const DIMENSION: usize>(
 pub type Line2<Frame> = Line<Frame, 2>;
 pub type Line3<Frame> = Line<Frame, 3>;
 
+pub const PARALLEL_ANGLE_TOLERANCE: f32 = 0.001;
+
 impl<Frame> Line2<Frame> {
+    /// Fits a line through the points minimizing the orthogonal distances (total least squares).
+    /// With `weights`, each squared distance is weighted by the weight of its point, e.g. the
+    /// confidence of an edge point, so that uncertain points barely pull the line.
+    /// Returns `None` if there are fewer than two points, all points coincide, the weights do not
+    /// match the points in number or sum up to zero.
+    pub fn from_points_least_squares(
+        points: &[Point2<Frame>],
+        weights: Option<&[f32]>,
+    ) -> Option<Self> {
+        if points.len() < 2 || weights.is_some_and(|weights| weights.len() != points.len()) {
+            return None;
+        }
+        let weight = |index: usize| weights.map_or(1.0, |weights| weights[index]);
+        let total_weight = (0..points.len()).map(weight).sum::<f32>();
+        if total_weight <= f32::EPSILON {
+            return None;
+        }
+        let center_x = points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| weight(index) * point.x())
+            .sum::<f32>()
+            / total_weight;
+        let center_y = points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| weight(index) * point.y())
+            .sum::<f32>()
+            / total_weight;
+        let (covariance_xx, covariance_xy, covariance_yy) = points.iter().enumerate().fold(
+            (0.0, 0.0, 0.0),
+            |(covariance_xx, covariance_xy, covariance_yy), (index, point)| {
+                let weight = weight(index);
+                let x = point.x() - center_x;
+                let y = point.y() - center_y;
+                (
+                    covariance_xx + weight * x * x,
+                    covariance_xy + weight * x * y,
+                    covariance_yy + weight * y * y,
+                )
+            },
+        );
+        if covariance_xx + covariance_yy <= f32::EPSILON {
+            return None;
+        }
+        let angle = 0.5 * f32::atan2(2.0 * covariance_xy, covariance_xx - covariance_yy);
+        let center = point![center_x, center_y];
+        Some(Line(center, center + vector![angle.cos(), angle.sin()]))
+    }
+
     pub fn signed_acute_angle(&self, other: Self) -> f32 {
         let self_direction = self.1 - self.0;
         let other_direction = other.1 - other.0;
@@ -29,6 +81,48 @@ impl<Frame> Line2<Frame> {
         (self.1 - self.0).angle(other.1 - other.0)
     }
 
+    /// Angle of the direction from the first to the second point relative to the x-axis, in
+    /// `(-PI, PI]`.
+    pub fn direction_angle(&self) -> f32 {
+        let direction = self.1 - self.0;
+        direction.y().atan2(direction.x())
+    }
+
+    /// Smallest angle between this line and a line with direction angle `angle`, ignoring the
+    /// direction of either line. Ranges from `0.0` to `PI / 2`.
+    pub fn acute_angle_to_direction(&self, angle: f32) -> f32 {
+        signed_acute_angle(self.1 - self.0, vector![angle.cos(), angle.sin()]).abs()
+    }
+
+    /// Orders the points such that the direction points towards positive x, or towards positive
+    /// y for vertical lines. A line and its reverse have the same canonical form.
+    pub fn canonicalize(self) -> Self {
+        let direction = self.1 - self.0;
+        if direction.x() < 0.0 || (direction.x() == 0.0 && direction.y() < 0.0) {
+            Line(self.1, self.0)
+        } else {
+            self
+        }
+    }
+
+    /// Whether both lines lie on approximately the same infinite line, regardless of their
+    /// directions: They enclose an angle of at most `angle_tolerance` and the points of each
+    /// line are at most `distance_tolerance` away from the other line.
+    pub fn is_approximately(
+        &self,
+        other: &Self,
+        angle_tolerance: f32,
+        distance_tolerance: f32,
+    ) -> bool {
+        signed_acute_angle(self.1 - self.0, other.1 - other.0).abs() <= angle_tolerance
+            && [other.0, other.1]
+                .into_iter()
+                .all(|point| self.distance_to_point(point) <= distance_tolerance)
+            && [self.0, self.1]
+                .into_iter()
+                .all(|point| other.distance_to_point(point) <= distance_tolerance)
+    }
+
     pub fn signed_acute_angle_to_orthogonal(&self, other: Self) -> f32 {
         let self_direction = self.1 - self.0;
         let other_direction = other.1 - other.0;
@@ -73,22 +167,21 @@ impl<Frame> Line2<Frame> {
         }
     }
 
-    pub fn intersection(&self, other: &Line2<Frame>) -> Point2<Frame> {
-        let x1 = self.0.x();
-        let y1 = self.0.y();
-        let x2 = self.1.x();
-        let y2 = self.1.y();
-        let x3 = other.0.x();
-        let y3 = other.0.y();
-        let x4 = other.1.x();
-        let y4 = other.1.y();
-
-        point!(
-            ((((x1 * y2) - (y1 * x2)) * (x3 - x4)) - ((x1 - x2) * ((x3 * y4) - (y3 * x4))))
-                / (((x1 - x2) * (y3 - y4)) - ((y1 - y2) * (x3 - x4))),
-            ((((x1 * y2) - (y1 * x2)) * (y3 - y4)) - ((y1 - y2) * ((x3 * y4) - (y3 * x4))))
-                / (((x1 - x2) * (y3 - y4)) - ((y1 - y2) * (x3 - x4)))
-        )
+    /// Returns `None` if the lines are parallel within [`PARALLEL_ANGLE_TOLERANCE`].
+    pub fn intersection(&self, other: &Line2<Frame>) -> Option<Point2<Frame>> {
+        let self_direction = self.1 - self.0;
+        let other_direction = other.1 - other.0;
+        let cross_product =
+            self_direction.x() * other_direction.y() - self_direction.y() * other_direction.x();
+        let sine_of_angle = cross_product / (self_direction.norm() * other_direction.norm());
+        if sine_of_angle.abs() <= PARALLEL_ANGLE_TOLERANCE.sin() {
+            return None;
+        }
+        let difference_of_starts = other.0 - self.0;
+        let t = (difference_of_starts.x() * other_direction.y()
+            - difference_of_starts.y() * other_direction.x())
+            / cross_product;
+        Some(self.0 + self_direction * t)
     }
 }
 
@@ -112,10 +205,15 @@ impl<Frame, const DIMENSION: usize> Line<Frame, DIMENSION> {
                 / difference_on_line.norm_squared())
     }
 
+    /// A segment whose end points coincide is treated as a single point.
     pub fn squared_distance_to_segment(&self, point: Point<Frame, DIMENSION>) -> f32 {
         let difference_on_line = self.1 - self.0;
         let difference_to_point = point - self.0;
-        let t = difference_to_point.dot(difference_on_line) / difference_on_line.norm_squared();
+        let squared_length = difference_on_line.norm_squared();
+        if squared_length == 0.0 {
+            return difference_to_point.norm_squared();
+        }
+        let t = difference_to_point.dot(difference_on_line) / squared_length;
         if t <= 0.0 {
             (point - self.0).norm_squared()
         } else if t >= 1.0 {
@@ -125,6 +223,28 @@ impl<Frame, const DIMENSION: usize> Line<Frame, DIMENSION> {
         }
     }
 
+    pub fn distance_to_segment(&self, point: Point<Frame, DIMENSION>) -> f32 {
+        self.squared_distance_to_segment(point).sqrt()
+    }
+
+    /// The segment of this line between the projections of the outermost `points` onto it.
+    /// Returns `None` if there are no points.
+    pub fn segment_spanning(&self, points: &[Point<Frame, DIMENSION>]) -> Option<Self> {
+        let difference_on_line = self.1 - self.0;
+        let squared_length = difference_on_line.norm_squared();
+        let (minimum, maximum) = points
+            .iter()
+            .map(|point| (*point - self.0).dot(difference_on_line) / squared_length)
+            .fold(None, |extent, t| match extent {
+                None => Some((t, t)),
+                Some((minimum, maximum)) => Some((f32::min(minimum, t), f32::max(maximum, t))),
+            })?;
+        Some(Line(
+            self.0 + difference_on_line * minimum,
+            self.0 + difference_on_line * maximum,
+        ))
+    }
+
     pub fn squared_distance_to_point(&self, point: Point<Frame, DIMENSION>) -> f32 {
         let closest_point = self.project_point(point);
         distance_squared(closest_point, point)
@@ -317,4 +437,212 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn intersection_of_perpendicular_lines() {
+        let horizontal: Line2<SomeFrame> = Line(point![-1.0, 2.0], point![5.0, 2.0]);
+        let vertical: Line2<SomeFrame> = Line(point![3.0, 7.0], point![3.0, 8.0]);
+
+        assert_relative_eq!(
+            horizontal
+                .intersection(&vertical)
+                .expect("lines should intersect"),
+            point![3.0, 2.0]
+        );
+        assert_relative_eq!(
+            vertical
+                .intersection(&horizontal)
+                .expect("lines should intersect"),
+            point![3.0, 2.0]
+        );
+    }
+
+    #[test]
+    fn intersection_of_near_parallel_lines() {
+        let line: Line2<SomeFrame> = Line(point![0.0, 0.0], point![1.0, 0.0]);
+        let almost_parallel: Line2<SomeFrame> = Line(point![0.0, 1.0], point![1.0, 1.0001]);
+        let slightly_tilted: Line2<SomeFrame> = Line(point![0.0, 1.0], point![1.0, 1.1]);
+
+        assert_eq!(line.intersection(&almost_parallel), None);
+        assert_relative_eq!(
+            line.intersection(&slightly_tilted)
+                .expect("lines should intersect"),
+            point![-10.0, 0.0],
+            epsilon = 0.001
+        );
+    }
+
+    #[test]
+    fn intersection_of_identical_lines() {
+        let line: Line2<SomeFrame> = Line(point![1.0, 1.0], point![2.0, 3.0]);
+
+        assert_eq!(line.intersection(&line), None);
+        assert_eq!(line.intersection(&Line(line.1, line.0)), None);
+    }
+
+    #[test]
+    fn least_squares_fit_of_collinear_points() {
+        let points: Vec<Point2<SomeFrame>> = (0..10)
+            .map(|x| point![x as f32, 2.0 * x as f32 - 3.0])
+            .collect();
+
+        let line = Line2::from_points_least_squares(&points, None).expect("no line was fitted");
+
+        assert_relative_eq!(line.slope(), 2.0, epsilon = 0.0001);
+        assert_relative_eq!(line.y_axis_intercept(), -3.0, epsilon = 0.0001);
+        for point in points {
+            assert_relative_eq!(line.distance_to_point(point), 0.0, epsilon = 0.0001);
+        }
+    }
+
+    #[test]
+    fn least_squares_fit_of_vertical_points() {
+        let points: Vec<Point2<SomeFrame>> = (0..5).map(|y| point![4.0, y as f32]).collect();
+
+        let line = Line2::from_points_least_squares(&points, None).expect("no line was fitted");
+
+        assert_relative_eq!(
+            line.distance_to_point(point![4.0, 100.0]),
+            0.0,
+            epsilon = 0.0001
+        );
+    }
+
+    #[test]
+    fn least_squares_fit_of_noisy_points() {
+        let noise = [0.1, -0.1, 0.05, -0.05, 0.0, 0.1, -0.1, 0.05, -0.05, 0.0];
+        let points: Vec<Point2<SomeFrame>> = noise
+            .iter()
+            .enumerate()
+            .map(|(x, noise)| point![x as f32, 0.5 * x as f32 + 1.0 + noise])
+            .collect();
+
+        let line = Line2::from_points_least_squares(&points, None).expect("no line was fitted");
+
+        assert_relative_eq!(line.slope(), 0.5, epsilon = 0.05);
+        assert_relative_eq!(line.y_axis_intercept(), 1.0, epsilon = 0.1);
+    }
+
+    #[test]
+    fn least_squares_fit_needs_two_distinct_points() {
+        assert_eq!(
+            Line2::<SomeFrame>::from_points_least_squares(&[], None),
+            None
+        );
+        assert_eq!(
+            Line2::<SomeFrame>::from_points_least_squares(&[point![1.0, 2.0]], None),
+            None
+        );
+        assert_eq!(
+            Line2::<SomeFrame>::from_points_least_squares(&[point![1.0, 2.0]; 3], None),
+            None
+        );
+    }
+
+    #[test]
+    fn low_weight_points_barely_affect_least_squares_fit() {
+        let mut points: Vec<Point2<SomeFrame>> = (0..10).map(|x| point![x as f32, 1.0]).collect();
+        points.push(point![4.5, 3.0]);
+        let mut weights = vec![1.0; 10];
+        weights.push(0.01);
+
+        let unweighted =
+            Line2::from_points_least_squares(&points, None).expect("no line was fitted");
+        let weighted =
+            Line2::from_points_least_squares(&points, Some(&weights)).expect("no line was fitted");
+
+        assert!(unweighted.y_axis_intercept() > 1.1);
+        assert_relative_eq!(weighted.slope(), 0.0, epsilon = 0.0001);
+        assert_relative_eq!(weighted.y_axis_intercept(), 1.0, epsilon = 0.01);
+    }
+
+    #[test]
+    fn weighted_least_squares_fit_needs_matching_positive_weights() {
+        let points: [Point2<SomeFrame>; 2] = [point![0.0, 0.0], point![1.0, 1.0]];
+
+        assert_eq!(
+            Line2::from_points_least_squares(&points, Some(&[1.0])),
+            None
+        );
+        assert_eq!(
+            Line2::from_points_least_squares(&points, Some(&[0.0, 0.0])),
+            None
+        );
+        assert!(Line2::from_points_least_squares(&points, Some(&[0.5, 2.0])).is_some());
+    }
+
+    #[test]
+    fn acute_angle_to_direction_ignores_line_direction() {
+        let line: Line2<SomeFrame> = Line(point![1.0, 1.0], point![0.0, 0.0]);
+
+        assert_relative_eq!(line.direction_angle(), -3.0 * FRAC_PI_4);
+        assert_relative_eq!(
+            line.acute_angle_to_direction(FRAC_PI_4),
+            0.0,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            line.acute_angle_to_direction(0.0),
+            FRAC_PI_4,
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(line.acute_angle_to_direction(PI), FRAC_PI_4, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn distance_to_segment_measures_to_closest_end_point() {
+        let segment = Line::<SomeFrame, 2>(point![0.0, 0.0], point![2.0, 0.0]);
+
+        assert_relative_eq!(segment.distance_to_segment(point![1.0, 1.0]), 1.0);
+        assert_relative_eq!(segment.distance_to_segment(point![5.0, 4.0]), 5.0);
+        assert_relative_eq!(segment.distance_to_segment(point![-3.0, 0.0]), 3.0);
+
+        let degenerate_segment = Line::<SomeFrame, 2>(point![1.0, 1.0], point![1.0, 1.0]);
+        assert_relative_eq!(
+            degenerate_segment.distance_to_segment(point![4.0, 5.0]),
+            5.0
+        );
+    }
+
+    #[test]
+    fn segment_spanning_covers_projections_of_outermost_points() {
+        let line = Line::<SomeFrame, 2>(point![0.0, 0.0], point![1.0, 1.0]);
+
+        assert_eq!(line.segment_spanning(&[]), None);
+        assert_relative_eq!(
+            line.segment_spanning(&[point![2.0, 0.0], point![-1.0, -3.0], point![4.0, 2.0]])
+                .unwrap(),
+            Line(point![-2.0, -2.0], point![3.0, 3.0])
+        );
+    }
+
+    #[test]
+    fn line_and_its_reverse_canonicalize_equal() {
+        for (start, end) in [
+            (point![1.0, 2.0], point![-3.0, 5.0]),
+            (point![0.0, 4.0], point![0.0, -1.0]),
+            (point![2.0, 2.0], point![3.0, 2.0]),
+        ] {
+            let line = Line::<SomeFrame, 2>(start, end).canonicalize();
+            let reversed = Line::<SomeFrame, 2>(end, start).canonicalize();
+
+            assert_eq!(line.0, reversed.0);
+            assert_eq!(line.1, reversed.1);
+            assert!(line.1.x() > line.0.x() || line.1.y() > line.0.y());
+        }
+    }
+
+    #[test]
+    fn reversed_and_shifted_lines_are_approximately_equal() {
+        let line = Line::<SomeFrame, 2>(point![0.0, 0.0], point![4.0, 0.0]);
+        let reversed = Line(point![6.0, 0.02], point![1.0, -0.01]);
+        let tilted = Line(point![0.0, 0.0], point![4.0, 0.4]);
+        let offset = Line(point![0.0, 0.5], point![4.0, 0.5]);
+
+        assert!(line.is_approximately(&reversed, 0.01, 0.05));
+        assert!(reversed.is_approximately(&line, 0.01, 0.05));
+        assert!(!line.is_approximately(&tilted, 0.01, 0.05));
+        assert!(!line.is_approximately(&offset, 0.01, 0.05));
+        assert!(line.is_approximately(&offset, 0.01, 0.5));
+    }
 }
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use linear_algebra::{Point2, Vector2};
+use linear_algebra::{point, Point2, Vector2};
 use serialize_hierarchy::SerializeHierarchy;
 
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
@@ -28,6 +28,19 @@ impl<Frame> Rectangle<Frame> {
         intersection_x * intersection_y
     }
 
+    pub fn clamp(self, bounds: Rectangle<Frame>) -> Self {
+        let clamp_point = |point: Point2<Frame>| {
+            point![
+                point.x().clamp(bounds.min.x(), bounds.max.x()),
+                point.y().clamp(bounds.min.y(), bounds.max.y())
+            ]
+        };
+        Self {
+            min: clamp_point(self.min),
+            max: clamp_point(self.max),
+        }
+    }
+
     pub fn area(self) -> f32 {
         let dimensions = self.max - self.min;
         dimensions.x() * dimensions.y()
@@ -79,6 +79,7 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                     "control::motion::step_planner",
                     "control::motion::walk_manager",
                     "control::motion::walking_engine",
+                    "control::motion::wave",
                     "control::obstacle_filter",
                     "control::odometry",
                     "control::orientation_filter",
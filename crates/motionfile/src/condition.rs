@@ -1,11 +1,16 @@
 use std::{fmt::Debug, time::Duration};
 
-use crate::{FallenAbort, StabilizedCondition};
+use crate::{
+    Cycles, ExcessiveAcceleration, FallenAbort, FootGrounded, GamePhase, JointPosition,
+    StabilizedCondition, StableGyro, TotalElapsed,
+};
 
 use enum_dispatch::enum_dispatch;
 use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
 use types::condition_input::ConditionInput;
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, SerializeHierarchy)]
 pub enum Response {
     Abort,
     Continue,
@@ -25,6 +30,32 @@ impl Response {
 #[enum_dispatch]
 pub trait Condition {
     fn evaluate(&self, condition_input: &ConditionInput) -> Response;
+
+    /// Evaluates the condition after it has been waited on for `cycles_since_start` cycles.
+    /// Only conditions counting cycles need to override this.
+    fn evaluate_in_cycle(
+        &self,
+        condition_input: &ConditionInput,
+        _cycles_since_start: usize,
+    ) -> Response {
+        self.evaluate(condition_input)
+    }
+
+    /// Evaluates the condition within a running motion, which has been advanced by
+    /// `elapsed_since_motion_start` since it started. Only conditions measuring the time of the
+    /// whole motion need to override this.
+    fn evaluate_in_motion(
+        &self,
+        condition_input: &ConditionInput,
+        cycles_since_start: usize,
+        _elapsed_since_motion_start: Duration,
+    ) -> Response {
+        self.evaluate_in_cycle(condition_input, cycles_since_start)
+    }
+
+    /// Forgets what the condition accumulated while being evaluated, so that a restarted motion
+    /// does not see the state of its previous run. Only stateful conditions need to override this.
+    fn reset(&self) {}
 }
 
 #[enum_dispatch]
@@ -35,11 +66,18 @@ pub trait TimeOut {
 #[enum_dispatch(Condition, TimeOut)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DiscreteConditionType {
+    Cycles,
+    FootGrounded,
+    GamePhase,
+    JointPosition,
     StabilizedCondition,
+    StableGyro,
+    TotalElapsed,
 }
 
 #[enum_dispatch(Condition)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ContinuousConditionType {
+    ExcessiveAcceleration,
     FallenAbort,
 }
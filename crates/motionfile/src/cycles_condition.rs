@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use serde::{Deserialize, Serialize};
+use types::condition_input::ConditionInput;
+
+/// Waits for the given number of cycles, independent of the cycle time. This keeps waits
+/// deterministic in replay, e.g. to let a filter settle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cycles(pub usize);
+
+impl Condition for Cycles {
+    /// Without knowing how many cycles passed, the condition can only wait.
+    fn evaluate(&self, _condition_input: &ConditionInput) -> Response {
+        Response::Wait
+    }
+
+    fn evaluate_in_cycle(
+        &self,
+        _condition_input: &ConditionInput,
+        cycles_since_start: usize,
+    ) -> Response {
+        if cycles_since_start >= self.0 {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+}
+
+impl TimeOut for Cycles {
+    fn timeout(&self, _time_since_start: Duration) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DiscreteConditionType;
+
+    use super::*;
+
+    #[test]
+    fn continues_after_given_number_of_cycles() {
+        let condition: DiscreteConditionType = serde_json::from_str(r#"{"Cycles": 3}"#).unwrap();
+        let condition_input = ConditionInput::default();
+
+        let responses: Vec<_> = (0..5)
+            .map(|cycles_since_start| {
+                condition.evaluate_in_cycle(&condition_input, cycles_since_start)
+            })
+            .collect();
+
+        assert_eq!(
+            responses,
+            [
+                Response::Wait,
+                Response::Wait,
+                Response::Wait,
+                Response::Continue,
+                Response::Continue
+            ]
+        );
+        assert!(!condition.timeout(Duration::from_secs(1000)));
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Debug;
+
+use crate::{condition::Response, Condition};
+
+use serde::{Deserialize, Serialize};
+use types::condition_input::ConditionInput;
+
+const GRAVITATIONAL_CONSTANT: f32 = 9.81;
+
+/// Aborts the motion once the measured acceleration deviates from gravity by more than
+/// `threshold`, e.g. because the robot is pushed hard. At rest, the accelerometer measures
+/// gravity in any orientation, so only the magnitude is compared.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcessiveAcceleration {
+    /// Deviation of the measured acceleration from gravity in m/s² at which the motion is aborted.
+    threshold: f32,
+}
+
+impl ExcessiveAcceleration {
+    pub fn new(threshold: f32) -> Self {
+        Self { threshold }
+    }
+}
+
+impl Condition for ExcessiveAcceleration {
+    fn evaluate(&self, condition_input: &ConditionInput) -> Response {
+        let deviation = (condition_input.linear_acceleration.norm() - GRAVITATIONAL_CONSTANT).abs();
+        if deviation > self.threshold {
+            Response::Abort
+        } else {
+            Response::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+
+    use super::*;
+
+    fn with_acceleration(x: f32, y: f32, z: f32) -> ConditionInput {
+        ConditionInput {
+            linear_acceleration: Vector3::new(x, y, z),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aborts_once_acceleration_crosses_threshold() {
+        let condition: ExcessiveAcceleration =
+            serde_json::from_str(r#"{ "threshold": 5.0 }"#).unwrap();
+
+        assert!(matches!(
+            condition.evaluate(&with_acceleration(0.0, 0.0, 9.81)),
+            Response::Continue
+        ));
+        assert!(matches!(
+            condition.evaluate(&with_acceleration(9.81, 0.0, 0.0)),
+            Response::Continue
+        ));
+        assert!(matches!(
+            condition.evaluate(&with_acceleration(4.0, 0.0, 13.0)),
+            Response::Continue
+        ));
+        assert!(matches!(
+            condition.evaluate(&with_acceleration(12.0, 0.0, 9.81)),
+            Response::Abort
+        ));
+        assert!(matches!(
+            condition.evaluate(&with_acceleration(0.0, 0.0, 4.0)),
+            Response::Abort
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use std::{fmt::Debug, time::Duration};
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use serde::{Deserialize, Serialize};
+use types::{condition_input::ConditionInput, support_foot::Side};
+
+/// Waits until the summed force sensitive resistor readings of `foot` exceed `threshold`, i.e.
+/// until the foot actually touches the ground.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FootGrounded {
+    foot: Side,
+    threshold: f32,
+    #[serde(with = "crate::serde_seconds")]
+    timeout_duration: Duration,
+}
+
+impl Condition for FootGrounded {
+    fn evaluate(&self, condition_input: &ConditionInput) -> Response {
+        let force_sensitive_resistors = &condition_input.force_sensitive_resistors;
+        let pressure = match self.foot {
+            Side::Left => force_sensitive_resistors.left.sum(),
+            Side::Right => force_sensitive_resistors.right.sum(),
+        };
+        if pressure > self.threshold {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+}
+
+impl TimeOut for FootGrounded {
+    fn timeout(&self, time_since_start: Duration) -> bool {
+        time_since_start > self.timeout_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DiscreteConditionType;
+
+    use super::*;
+
+    fn condition_input_with_pressure(left: f32, right: f32) -> ConditionInput {
+        let mut condition_input = ConditionInput::default();
+        condition_input.force_sensitive_resistors.left.front_left = left;
+        condition_input.force_sensitive_resistors.right.rear_right = right;
+        condition_input
+    }
+
+    #[test]
+    fn waits_until_foot_pressure_exceeds_threshold() {
+        let condition: DiscreteConditionType = serde_json::from_str(
+            r#"{ "FootGrounded": { "foot": "Right", "threshold": 0.5, "timeout_duration": 1.0 } }"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_pressure(0.0, 0.2)),
+            Response::Wait
+        ));
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_pressure(1.0, 0.2)),
+            Response::Wait
+        ));
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_pressure(0.0, 0.8)),
+            Response::Continue
+        ));
+        assert!(condition.timeout(Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,59 @@
+use std::{fmt::Debug, time::Duration};
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use serde::{Deserialize, Serialize};
+use types::{condition_input::ConditionInput, primary_state::PrimaryState};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GamePhase {
+    phase: PrimaryState,
+    #[serde(with = "crate::serde_seconds")]
+    timeout_duration: Duration,
+}
+
+impl Condition for GamePhase {
+    fn evaluate(&self, condition_input: &ConditionInput) -> Response {
+        if condition_input.primary_state == self.phase {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+}
+
+impl TimeOut for GamePhase {
+    fn timeout(&self, time_since_start: Duration) -> bool {
+        time_since_start > self.timeout_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continues_only_in_requested_phase() {
+        let condition = GamePhase {
+            phase: PrimaryState::Playing,
+            timeout_duration: Duration::from_secs(1),
+        };
+
+        for (primary_state, should_continue) in [
+            (PrimaryState::Initial, false),
+            (PrimaryState::Ready, false),
+            (PrimaryState::Set, false),
+            (PrimaryState::Playing, true),
+            (PrimaryState::Finished, false),
+        ] {
+            let condition_input = ConditionInput {
+                primary_state,
+                ..Default::default()
+            };
+            assert_eq!(
+                matches!(condition.evaluate(&condition_input), Response::Continue),
+                should_continue,
+                "{primary_state:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::{fmt::Debug, time::Duration};
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use serde::{Deserialize, Serialize};
+use types::{condition_input::ConditionInput, joints::JointsName};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub enum Comparison {
+    LessThan,
+    GreaterThan,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JointPosition {
+    joint: JointsName,
+    comparison: Comparison,
+    value: f32,
+    #[serde(with = "crate::serde_seconds")]
+    timeout_duration: Duration,
+}
+
+impl Condition for JointPosition {
+    fn evaluate(&self, condition_input: &ConditionInput) -> Response {
+        let position = condition_input.positions[self.joint];
+        let is_reached = match self.comparison {
+            Comparison::LessThan => position < self.value,
+            Comparison::GreaterThan => position > self.value,
+        };
+        if is_reached {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+}
+
+impl TimeOut for JointPosition {
+    fn timeout(&self, time_since_start: Duration) -> bool {
+        time_since_start > self.timeout_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::joints::leg::LegJoint;
+
+    use crate::DiscreteConditionType;
+
+    use super::*;
+
+    fn condition_input_with_left_knee(knee_pitch: f32) -> ConditionInput {
+        let mut condition_input = ConditionInput::default();
+        condition_input.positions.left_leg.knee_pitch = knee_pitch;
+        condition_input
+    }
+
+    #[test]
+    fn waits_until_joint_passes_threshold() {
+        let condition = JointPosition {
+            joint: JointsName::LeftLeg(LegJoint::KneePitch),
+            comparison: Comparison::GreaterThan,
+            value: 1.5,
+            timeout_duration: Duration::from_secs(1),
+        };
+
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_left_knee(1.0)),
+            Response::Wait
+        ));
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_left_knee(1.6)),
+            Response::Continue
+        ));
+
+        let condition = JointPosition {
+            comparison: Comparison::LessThan,
+            ..condition
+        };
+        assert!(matches!(
+            condition.evaluate(&condition_input_with_left_knee(1.0)),
+            Response::Continue
+        ));
+    }
+
+    #[test]
+    fn deserializes_from_motion_file_syntax() {
+        let json = r#"{
+            "JointPosition": {
+                "joint": { "LeftLeg": "KneePitch" },
+                "comparison": "GreaterThan",
+                "value": 1.5,
+                "timeout_duration": 2.0
+            }
+        }"#;
+
+        let condition: DiscreteConditionType = serde_json::from_str(json).unwrap();
+        let DiscreteConditionType::JointPosition(condition) = condition else {
+            panic!("wrong condition type: {condition:?}");
+        };
+        assert_eq!(condition.joint, JointsName::LeftLeg(LegJoint::KneePitch));
+        assert_eq!(condition.comparison, Comparison::GreaterThan);
+        assert_eq!(condition.value, 1.5);
+        assert_eq!(condition.timeout_duration, Duration::from_secs(2));
+
+        let serialized = serde_json::to_value(&condition).unwrap();
+        let round_tripped: JointPosition = serde_json::from_value(serialized).unwrap();
+        assert_eq!(round_tripped.joint, condition.joint);
+        assert_eq!(round_tripped.comparison, condition.comparison);
+        assert_eq!(round_tripped.value, condition.value);
+        assert_eq!(round_tripped.timeout_duration, condition.timeout_duration);
+    }
+}
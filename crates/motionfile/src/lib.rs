@@ -1,15 +1,32 @@
 mod condition;
+pub mod cycles_condition;
+pub mod excessive_acceleration_condition;
 pub mod fallen_abort_condition;
+pub mod foot_grounded_condition;
+pub mod game_phase_condition;
+pub mod joint_position_condition;
+pub mod mirrored_interpolator;
 pub mod motion_file;
 pub mod motion_interpolator;
+mod serde_seconds;
 pub mod spline_interpolator;
 pub mod stabilized_condition;
+pub mod stable_gyro_condition;
 pub mod timed_spline;
+pub mod total_elapsed_condition;
 
 pub use condition::{Condition, ContinuousConditionType, DiscreteConditionType, Response, TimeOut};
+pub use cycles_condition::Cycles;
+pub use excessive_acceleration_condition::ExcessiveAcceleration;
 pub use fallen_abort_condition::FallenAbort;
+pub use foot_grounded_condition::FootGrounded;
+pub use game_phase_condition::GamePhase;
+pub use joint_position_condition::JointPosition;
+pub use mirrored_interpolator::MirroredInterpolator;
 pub use motion_file::*;
-pub use motion_interpolator::MotionInterpolator;
+pub use motion_interpolator::{ConditionEvaluation, ConditionKind, MotionInterpolator};
 pub use spline_interpolator::SplineInterpolator;
 pub use stabilized_condition::StabilizedCondition;
-pub use timed_spline::TimedSpline;
+pub use stable_gyro_condition::StableGyro;
+pub use timed_spline::{SplineScalar, TimedSpline};
+pub use total_elapsed_condition::TotalElapsed;
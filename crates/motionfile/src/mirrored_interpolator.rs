@@ -0,0 +1,87 @@
+use std::fmt::Debug;
+
+use crate::{ContinuousConditionType, MotionFile, MotionInterpolator, SplineScalar};
+use color_eyre::{Report, Result};
+use serde::{Deserialize, Serialize};
+use splines::Interpolate;
+use types::{fall_state::Side, joints::mirror::Mirror};
+
+/// A motion designed for lying on the right side together with its mirrored variant for the left
+/// side. The variant is chosen once per execution, since switching during the motion would make
+/// the joints jump.
+#[derive(Default, Debug, Deserialize, Serialize)]
+pub struct MirroredInterpolator<T, S = f32> {
+    original: MotionInterpolator<T, S>,
+    mirrored: MotionInterpolator<T, S>,
+    /// Side the robot was lying on when the motion started, `None` while not executing.
+    side: Option<Side>,
+}
+
+impl<T: Debug + Interpolate<S>, S: SplineScalar> MirroredInterpolator<T, S> {
+    pub fn side(&self) -> Option<Side> {
+        self.side
+    }
+
+    /// Keeps the side chosen earlier in this execution and otherwise chooses it from `roll`.
+    pub fn select_side(&mut self, roll: f32) {
+        self.side.get_or_insert_with(|| Side::from_roll(roll));
+    }
+
+    /// Chooses the side from `roll` even if one was chosen earlier, e.g. because the robot
+    /// rolled over during an interruption.
+    pub fn reselect_side(&mut self, roll: f32) {
+        self.side = Some(Side::from_roll(roll));
+    }
+
+    /// Resets both variants and forgets the side, so that the next execution starts over.
+    pub fn reset(&mut self) {
+        self.original.reset();
+        self.mirrored.reset();
+        self.side = None;
+    }
+
+    pub fn original(&self) -> &MotionInterpolator<T, S> {
+        &self.original
+    }
+
+    pub fn mirrored(&self) -> &MotionInterpolator<T, S> {
+        &self.mirrored
+    }
+
+    /// The variant for the selected side, the original one while no side is selected.
+    pub fn active(&self) -> &MotionInterpolator<T, S> {
+        match self.side {
+            Some(Side::Left) => &self.mirrored,
+            Some(Side::Right) | None => &self.original,
+        }
+    }
+
+    pub fn active_mut(&mut self) -> &mut MotionInterpolator<T, S> {
+        match self.side {
+            Some(Side::Left) => &mut self.mirrored,
+            Some(Side::Right) | None => &mut self.original,
+        }
+    }
+
+    /// Adds `condition` to both variants, see [`MotionInterpolator::add_interrupt_condition`].
+    pub fn add_interrupt_condition(&mut self, condition: ContinuousConditionType) {
+        self.original.add_interrupt_condition(condition.clone());
+        self.mirrored.add_interrupt_condition(condition);
+    }
+}
+
+impl<T, S> TryFrom<MotionFile<T>> for MirroredInterpolator<T, S>
+where
+    T: Clone + Debug + Interpolate<S> + Mirror,
+    S: SplineScalar,
+{
+    type Error = Report;
+
+    fn try_from(motion_file: MotionFile<T>) -> Result<Self> {
+        Ok(Self {
+            original: MotionInterpolator::try_from(motion_file.clone())?,
+            mirrored: MotionInterpolator::try_from(motion_file)?.mirrored(),
+            side: None,
+        })
+    }
+}
@@ -1,38 +1,284 @@
 use std::fmt::Debug;
-use std::{fs::File, path::Path, time::Duration};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use color_eyre::eyre::{Result, WrapErr};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::from_reader;
+use color_eyre::eyre::{bail, eyre, Report, Result, WrapErr};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::{from_reader, from_value, json, Value};
 use splines::{Interpolate, Interpolation};
+use types::{joints::Joints, motor_commands::MotorCommands};
 
-use crate::condition::{ContinuousConditionType, DiscreteConditionType};
+use crate::{
+    condition::{ContinuousConditionType, DiscreteConditionType},
+    timed_spline::{is_supported_interpolation_mode, SUPPORTED_INTERPOLATION_MODES},
+};
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+const IN_MEMORY_ORIGIN: &str = "<in memory>";
+
+/// Schema version of motion files written by this crate. Files without a `version` field are
+/// version 1 and are migrated when loading.
+pub const CURRENT_MOTION_FILE_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MotionFile<T> {
+    pub version: u32,
     #[serde(default)]
     pub interpolation_mode: Interpolation<Duration, T>,
     pub initial_positions: T,
     pub motion: Vec<MotionFileFrame<T>>,
 }
 
+impl<T: Default> Default for MotionFile<T> {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: Default::default(),
+            motion: Default::default(),
+        }
+    }
+}
+
+/// A motion file as stored on disk, where frames may be replaced by other motion files whose
+/// frames are spliced in at their position. Older versions are migrated to this schema before
+/// deserializing, see [`migrate`].
+#[derive(Deserialize)]
+struct MotionFileSource<T> {
+    interpolation_mode: Interpolation<Duration, T>,
+    initial_positions: T,
+    motion: Vec<MotionFileEntry<T>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MotionFileEntry<T> {
+    Include { include: PathBuf },
+    Frame(MotionFileFrame<T>),
+}
+
 impl<T> MotionFile<T>
 where
     for<'de> T: Debug + Interpolate<f32> + Deserialize<'de> + Default,
 {
     pub fn from_path(motion_file_path: impl AsRef<Path>) -> Result<Self> {
-        let file = File::open(&motion_file_path).wrap_err_with(|| {
-            format!("failed to open motion file {:?}", motion_file_path.as_ref())
-        })?;
-        from_reader(file).wrap_err_with(|| {
-            format!(
-                "failed to parse motion file {:?}",
-                motion_file_path.as_ref()
-            )
+        Self::from_path_with_including_files(motion_file_path.as_ref(), &mut Vec::new())
+    }
+
+    /// Parses a motion file, e.g. one embedded into the binary. Included motion files are
+    /// resolved relative to the current directory.
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        let source = parse_source(
+            from_reader(reader).wrap_err("failed to parse motion file")?,
+            IN_MEMORY_ORIGIN,
+        )?;
+        Self::from_source(source, IN_MEMORY_ORIGIN, Path::new(""), &mut Vec::new())
+    }
+
+    fn from_path_with_including_files(
+        motion_file_path: &Path,
+        including_files: &mut Vec<PathBuf>,
+    ) -> Result<Self> {
+        let file = File::open(motion_file_path)
+            .wrap_err_with(|| format!("failed to open motion file {motion_file_path:?}"))?;
+        let canonical_path = motion_file_path
+            .canonicalize()
+            .wrap_err_with(|| format!("failed to resolve motion file {motion_file_path:?}"))?;
+        if including_files.contains(&canonical_path) {
+            bail!("motion file {motion_file_path:?} includes itself via {including_files:?}");
+        }
+        let origin = format!("{motion_file_path:?}");
+        let source = parse_source(
+            from_reader(file)
+                .wrap_err_with(|| format!("failed to parse motion file {motion_file_path:?}"))?,
+            &origin,
+        )?;
+
+        including_files.push(canonical_path);
+        let motion_file = Self::from_source(
+            source,
+            &origin,
+            motion_file_path.parent().unwrap_or(Path::new("")),
+            including_files,
+        );
+        including_files.pop();
+        motion_file
+    }
+
+    fn from_source(
+        source: MotionFileSource<T>,
+        origin: &str,
+        directory: &Path,
+        including_files: &mut Vec<PathBuf>,
+    ) -> Result<Self> {
+        if !is_supported_interpolation_mode(&source.interpolation_mode) {
+            bail!(
+                "motion file {origin} uses unsupported interpolation mode {:?}, \
+                 supported are {}",
+                source.interpolation_mode,
+                SUPPORTED_INTERPOLATION_MODES.join(", ")
+            );
+        }
+
+        let mut motion = Vec::new();
+        for entry in source.motion {
+            match entry {
+                MotionFileEntry::Include { include } => {
+                    let included_file = Self::from_path_with_including_files(
+                        &directory.join(&include),
+                        including_files,
+                    )
+                    .wrap_err_with(|| {
+                        format!("failed to include {include:?} in motion file {origin}")
+                    })?;
+                    motion.extend(included_file.motion);
+                }
+                MotionFileEntry::Frame(frame) => motion.push(frame),
+            }
+        }
+
+        Ok(Self {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: source.interpolation_mode,
+            initial_positions: source.initial_positions,
+            motion,
         })
     }
 }
 
+fn parse_source<T>(motion_file: Value, origin: &str) -> Result<MotionFileSource<T>>
+where
+    for<'de> T: Deserialize<'de>,
+{
+    let motion_file = migrate(motion_file, origin)?;
+    from_value(motion_file).wrap_err_with(|| format!("failed to parse motion file {origin}"))
+}
+
+/// Upgrades a motion file to [`CURRENT_MOTION_FILE_VERSION`] step by step, so each schema change
+/// only needs a migration from its predecessor.
+fn migrate(mut motion_file: Value, origin: &str) -> Result<Value> {
+    loop {
+        let version = match motion_file.get("version") {
+            None => 1,
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| eyre!("motion file {origin} has invalid version {version}"))?,
+        };
+        match version {
+            1 => migrate_from_version_1(&mut motion_file).wrap_err_with(|| {
+                format!("failed to migrate motion file {origin} from version 1")
+            })?,
+            CURRENT_MOTION_FILE_VERSION => return Ok(motion_file),
+            _ => bail!(
+                "motion file {origin} has unknown version {version}, \
+                 supported are versions up to {CURRENT_MOTION_FILE_VERSION}"
+            ),
+        }
+    }
+}
+
+/// Version 1 files omit the version, the interpolation mode (linear) and empty interrupt
+/// conditions of frames. Version 2 states them explicitly.
+fn migrate_from_version_1(motion_file: &mut Value) -> Result<()> {
+    let Value::Object(motion_file) = motion_file else {
+        bail!("motion file is not an object");
+    };
+    motion_file.insert("version".to_string(), json!(2));
+    motion_file
+        .entry("interpolation_mode")
+        .or_insert_with(|| json!("linear"));
+    if let Some(Value::Array(motion)) = motion_file.get_mut("motion") {
+        for entry in motion {
+            if let Value::Object(frame) = entry {
+                if !frame.contains_key("include") {
+                    frame
+                        .entry("interrupt_conditions")
+                        .or_insert_with(|| json!([]));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl<T> FromStr for MotionFile<T>
+where
+    for<'de> T: Debug + Interpolate<f32> + Deserialize<'de> + Default,
+{
+    type Err = Report;
+
+    fn from_str(motion_file: &str) -> Result<Self> {
+        Self::from_reader(motion_file.as_bytes())
+    }
+}
+
+impl<T: JointDeviations> MotionFile<T> {
+    /// Checks that each frame continues where the previous one ended. A frame starting with a
+    /// zero-duration keyframe jumps to that keyframe's positions, which must match the last
+    /// positions of the previous frame within `tolerance`.
+    ///
+    /// This is not checked when loading a motion file, so that robots keep running motions with
+    /// small authoring mistakes. The motions in `etc/motions` are checked strictly by the tests.
+    pub fn validate_continuity(&self, tolerance: f32) -> Result<()> {
+        let discontinuities = self
+            .motion
+            .iter()
+            .enumerate()
+            .tuple_windows()
+            .filter_map(|((_, previous_frame), (frame_index, frame))| {
+                let end = &previous_frame.keyframes.last()?.positions;
+                let start = frame.keyframes.first()?;
+                if !start.duration.is_zero() {
+                    return None;
+                }
+                let deviations = end.deviations(&start.positions);
+                let joints = deviations
+                    .into_iter()
+                    .filter(|(_, deviation)| deviation.abs() > tolerance)
+                    .map(|(joint, deviation)| format!("{joint} jumps by {deviation}"))
+                    .collect_vec();
+                (!joints.is_empty()).then(|| format!("frame {frame_index}: {}", joints.join(", ")))
+            })
+            .collect_vec();
+        if !discontinuities.is_empty() {
+            bail!("motion is discontinuous in {}", discontinuities.join("; "));
+        }
+        Ok(())
+    }
+}
+
+/// Per-joint differences between two sets of positions, used to report discontinuous joints.
+pub trait JointDeviations {
+    fn deviations(&self, other: &Self) -> Vec<(String, f32)>;
+}
+
+impl JointDeviations for f32 {
+    fn deviations(&self, other: &Self) -> Vec<(String, f32)> {
+        vec![("position".to_string(), other - self)]
+    }
+}
+
+impl JointDeviations for Joints<f32> {
+    fn deviations(&self, other: &Self) -> Vec<(String, f32)> {
+        self.enumerate()
+            .zip(*other)
+            .map(|((joint, position), other)| (format!("{joint:?}"), other - position))
+            .collect()
+    }
+}
+
+impl JointDeviations for MotorCommands<Joints<f32>> {
+    fn deviations(&self, other: &Self) -> Vec<(String, f32)> {
+        self.positions.deviations(&other.positions)
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MotionFileFrame<T> {
     pub name: Option<String>,
@@ -45,24 +291,232 @@ pub struct MotionFileFrame<T> {
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct KeyFrame<T> {
-    #[serde(
-        serialize_with = "serialize_float_seconds",
-        deserialize_with = "deserialize_float_seconds"
-    )]
+    #[serde(with = "crate::serde_seconds")]
     pub duration: Duration,
     pub positions: T,
 }
 
-fn serialize_float_seconds<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    serializer.serialize_f32(duration.as_secs_f32())
-}
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
 
-fn deserialize_float_seconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    Ok(Duration::from_secs_f32(f32::deserialize(deserializer)?))
+    use super::*;
+
+    fn motion_file_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir()
+            .join(format!("motionfile_tests_{}", std::process::id()))
+            .join(name);
+        create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn motion_file_with(entries: &str) -> String {
+        format!(r#"{{ "initial_positions": 0.0, "motion": [{entries}] }}"#)
+    }
+
+    fn frame_with_keyframes(keyframes: &[(f32, f32)]) -> MotionFileFrame<f32> {
+        MotionFileFrame {
+            name: None,
+            entry_condition: None,
+            interrupt_conditions: vec![],
+            keyframes: keyframes
+                .iter()
+                .map(|&(duration, positions)| KeyFrame {
+                    duration: Duration::from_secs_f32(duration),
+                    positions,
+                })
+                .collect(),
+            exit_condition: None,
+        }
+    }
+
+    fn frame(position: f32) -> String {
+        format!(r#"{{ "keyframes": [{{ "duration": 1.0, "positions": {position} }}] }}"#)
+    }
+
+    #[test]
+    fn included_frames_are_spliced_in() {
+        let directory = motion_file_directory("splice");
+        create_dir_all(directory.join("common")).unwrap();
+        write(
+            directory.join("common/tuck.json"),
+            motion_file_with(&[frame(2.0), frame(3.0)].join(",")),
+        )
+        .unwrap();
+        write(
+            directory.join("stand_up.json"),
+            motion_file_with(
+                &[
+                    frame(1.0),
+                    r#"{ "include": "common/tuck.json" }"#.to_string(),
+                    frame(4.0),
+                ]
+                .join(","),
+            ),
+        )
+        .unwrap();
+
+        let motion_file = MotionFile::<f32>::from_path(directory.join("stand_up.json")).unwrap();
+
+        let positions: Vec<f32> = motion_file
+            .motion
+            .iter()
+            .map(|frame| frame.keyframes[0].positions)
+            .collect();
+        assert_eq!(positions, vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn motion_files_are_parsed_from_strings() {
+        let motion_file: MotionFile<f32> = motion_file_with(&[frame(1.0), frame(2.0)].join(","))
+            .parse()
+            .unwrap();
+
+        let positions: Vec<f32> = motion_file
+            .motion
+            .iter()
+            .map(|frame| frame.keyframes[0].positions)
+            .collect();
+        assert_eq!(positions, vec![1.0, 2.0]);
+
+        let error = MotionFile::<f32>::from_str(r#"{ "motion": [] }"#).unwrap_err();
+        assert!(format!("{error:?}").contains("failed to parse motion file"));
+    }
+
+    #[test]
+    fn version_1_files_are_migrated_with_defaults() {
+        let motion_file: MotionFile<f32> = motion_file_with(&frame(1.0)).parse().unwrap();
+
+        assert_eq!(motion_file.version, CURRENT_MOTION_FILE_VERSION);
+        assert!(matches!(
+            motion_file.interpolation_mode,
+            Interpolation::Linear
+        ));
+        assert!(motion_file.motion[0].interrupt_conditions.is_empty());
+        assert_eq!(motion_file.motion[0].keyframes[0].positions, 1.0);
+    }
+
+    #[test]
+    fn current_version_requires_an_explicit_interpolation_mode() {
+        let motion_file = format!(
+            r#"{{ "version": 2, "interpolation_mode": "cosine", "initial_positions": 0.0, "motion": [{}] }}"#,
+            frame(1.0)
+        );
+        let motion_file: MotionFile<f32> = motion_file.parse().unwrap();
+        assert!(matches!(
+            motion_file.interpolation_mode,
+            Interpolation::Cosine
+        ));
+
+        let motion_file = format!(
+            r#"{{ "version": 2, "initial_positions": 0.0, "motion": [{}] }}"#,
+            frame(1.0)
+        );
+        assert!(MotionFile::<f32>::from_str(&motion_file).is_err());
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        let motion_file = format!(
+            r#"{{ "version": 3, "initial_positions": 0.0, "motion": [{}] }}"#,
+            frame(1.0)
+        );
+
+        let error = MotionFile::<f32>::from_str(&motion_file).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "motion file <in memory> has unknown version 3, supported are versions up to 2"
+        );
+    }
+
+    #[test]
+    fn cyclic_includes_are_rejected() {
+        let directory = motion_file_directory("cycle");
+        write(
+            directory.join("first.json"),
+            motion_file_with(r#"{ "include": "second.json" }"#),
+        )
+        .unwrap();
+        write(
+            directory.join("second.json"),
+            motion_file_with(&[frame(1.0), r#"{ "include": "first.json" }"#.to_string()].join(",")),
+        )
+        .unwrap();
+
+        let error = MotionFile::<f32>::from_path(directory.join("first.json")).unwrap_err();
+
+        assert!(format!("{error:?}").contains("includes itself"));
+    }
+
+    #[test]
+    fn unsupported_interpolation_modes_are_rejected_when_loading() {
+        let directory = motion_file_directory("interpolation_mode");
+        write(
+            directory.join("bezier.json"),
+            format!(
+                r#"{{ "interpolation_mode": {{ "bezier": 0.5 }}, "initial_positions": 0.0, "motion": [{}] }}"#,
+                frame(1.0)
+            ),
+        )
+        .unwrap();
+        write(
+            directory.join("cosine.json"),
+            format!(
+                r#"{{ "interpolation_mode": "cosine", "initial_positions": 0.0, "motion": [{}] }}"#,
+                frame(1.0)
+            ),
+        )
+        .unwrap();
+
+        let error = MotionFile::<f32>::from_path(directory.join("bezier.json")).unwrap_err();
+
+        assert!(error.to_string().contains("bezier.json"));
+        assert!(error.to_string().contains("Bezier(0.5)"));
+        assert!(MotionFile::<f32>::from_path(directory.join("cosine.json")).is_ok());
+    }
+
+    #[test]
+    fn jumps_between_frames_are_reported() {
+        let motion_file = MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: 0.0,
+            motion: vec![
+                frame_with_keyframes(&[(1.0, 1.0), (1.0, 2.0)]),
+                frame_with_keyframes(&[(0.0, 2.005), (1.0, 3.0)]),
+                frame_with_keyframes(&[(0.0, 3.5), (1.0, 4.0)]),
+                frame_with_keyframes(&[(1.0, 0.0)]),
+            ],
+        };
+
+        assert!(motion_file.validate_continuity(1.0).is_ok());
+        let error = motion_file.validate_continuity(0.01).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "motion is discontinuous in frame 2: position jumps by 0.5"
+        );
+    }
+
+    #[test]
+    fn motions_are_continuous() {
+        let motions = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../etc/motions");
+        for entry in std::fs::read_dir(motions).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                let result = match MotionFile::<Joints<f32>>::from_path(&path) {
+                    Ok(motion_file) => motion_file.validate_continuity(1e-3),
+                    Err(_) => MotionFile::<MotorCommands<Joints<f32>>>::from_path(&path)
+                        .unwrap()
+                        .validate_continuity(1e-3),
+                };
+                if let Err(error) = result {
+                    panic!("{path:?}: {error}");
+                }
+            }
+        }
+    }
 }
@@ -1,36 +1,72 @@
 use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Sub};
 use std::time::Duration;
 
 use crate::{
     condition::{ContinuousConditionType, DiscreteConditionType, Response, TimeOut},
-    timed_spline::{InterpolatorError, TimedSpline},
+    timed_spline::{SplineScalar, TimedSpline},
     Condition, MotionFile,
 };
-use color_eyre::{Report, Result};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Report, Result,
+};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
+use serialize_hierarchy::SerializeHierarchy;
 use splines::Interpolate;
-use types::condition_input::ConditionInput;
+use types::{condition_input::ConditionInput, joints::mirror::Mirror};
 
 #[derive(Debug, Default, Deserialize, Serialize)]
-pub struct ConditionedSpline<T> {
+pub struct ConditionedSpline<T, S = f32> {
     pub entry_condition: Option<DiscreteConditionType>,
     pub interrupt_conditions: Vec<ContinuousConditionType>,
-    pub spline: TimedSpline<T>,
+    pub spline: TimedSpline<T, S>,
     pub exit_condition: Option<DiscreteConditionType>,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
-pub struct MotionInterpolator<T> {
-    frames: Vec<ConditionedSpline<T>>,
+pub struct MotionInterpolator<T, S = f32> {
+    frames: Vec<ConditionedSpline<T, S>>,
+    blend: Option<TimedSpline<T, S>>,
+    /// Conditions of [`Self::add_interrupt_condition`], which also guard the blend and the
+    /// connection frames of [`Self::append`].
+    interrupt_conditions: Vec<ContinuousConditionType>,
+    hold_final: bool,
+    /// Factors of the last [`Self::set_frame_time_scale`], frames without one are unscaled.
+    frame_time_scales: Vec<f32>,
     current_state: State<T>,
+    /// Time the motion advanced since it started, see [`Self::elapsed`].
+    elapsed: Duration,
+    #[serde(skip)]
+    last_condition_evaluation: Option<ConditionEvaluation>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, SerializeHierarchy)]
+pub enum ConditionKind {
+    Entry,
+    Interrupt,
+    Exit,
+}
+
+/// Result of the most recent condition evaluated by a [`MotionInterpolator`]. For interrupt
+/// conditions, the response is the combined one of all interrupt conditions of the frame.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Serialize, SerializeHierarchy)]
+pub struct ConditionEvaluation {
+    pub frame_index: usize,
+    pub kind: ConditionKind,
+    pub response: Response,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 enum State<T> {
+    Blend {
+        time_since_start: Duration,
+    },
     CheckEntry {
         current_frame_index: usize,
         time_since_start: Duration,
+        cycles_since_start: usize,
     },
     InterpolateSpline {
         current_frame_index: usize,
@@ -39,7 +75,9 @@ enum State<T> {
     CheckExit {
         current_frame_index: usize,
         time_since_start: Duration,
+        cycles_since_start: usize,
     },
+    HoldingFinal,
     Finished,
     Aborted {
         at_position: T,
@@ -80,18 +118,22 @@ impl<T> Default for State<T> {
         State::CheckEntry {
             current_frame_index: 0,
             time_since_start: Duration::ZERO,
+            cycles_since_start: 0,
         }
     }
 }
 
-impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
+impl<T: Debug + Interpolate<S>, S: SplineScalar> MotionInterpolator<T, S> {
     fn check_continuous_conditions(&mut self, condition_input: &ConditionInput) -> ReturnState {
-        if let Some(continuous_conditions) = self
-            .current_state
-            .current_frame_index()
-            .map(|frame_index| &self.frames[frame_index].interrupt_conditions)
-        {
-            return match continuous_conditions
+        let interrupt_conditions = match self.current_state {
+            State::Blend { .. } => Some((0, &self.interrupt_conditions)),
+            _ => self
+                .current_state
+                .current_frame_index()
+                .map(|frame_index| (frame_index, &self.frames[frame_index].interrupt_conditions)),
+        };
+        if let Some((frame_index, interrupt_conditions)) = interrupt_conditions {
+            let response = interrupt_conditions
                 .iter()
                 .map(|condition| condition.evaluate(condition_input))
                 .reduce(|accumulated, current| match (&accumulated, &current) {
@@ -100,7 +142,15 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
                     (Response::Wait, _) => Response::Wait,
                     (_, Response::Wait) => Response::Wait,
                     _ => accumulated,
-                }) {
+                });
+            if let Some(response) = response {
+                self.last_condition_evaluation = Some(ConditionEvaluation {
+                    frame_index,
+                    kind: ConditionKind::Interrupt,
+                    response,
+                });
+            }
+            return match response {
                 Some(Response::Abort) => {
                     self.current_state = State::Aborted {
                         at_position: self.value(),
@@ -117,22 +167,49 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
 
     fn advance_state(&mut self, time_step: Duration, condition_input: &ConditionInput) {
         self.current_state = match self.current_state {
+            State::Blend { time_since_start } => {
+                let blend_duration = self
+                    .blend
+                    .as_ref()
+                    .map_or(Duration::ZERO, |blend| blend.total_duration());
+                if time_since_start >= blend_duration {
+                    State::CheckEntry {
+                        current_frame_index: 0,
+                        time_since_start: Duration::ZERO,
+                        cycles_since_start: 0,
+                    }
+                } else {
+                    State::Blend {
+                        time_since_start: time_since_start + time_step,
+                    }
+                }
+            }
             State::CheckEntry {
                 current_frame_index,
                 time_since_start,
+                cycles_since_start,
             } => {
                 let current_frame = &self.frames[current_frame_index];
-                match current_frame.entry_condition.as_ref().map(|condition| {
+                let response = current_frame.entry_condition.as_ref().map(|condition| {
                     condition
-                        .evaluate(condition_input)
+                        .evaluate_in_motion(condition_input, cycles_since_start, self.elapsed)
                         .with_timeout(condition.timeout(time_since_start))
-                }) {
+                });
+                if let Some(response) = response {
+                    self.last_condition_evaluation = Some(ConditionEvaluation {
+                        frame_index: current_frame_index,
+                        kind: ConditionKind::Entry,
+                        response,
+                    });
+                }
+                match response {
                     Some(Response::Abort) => State::Aborted {
                         at_position: self.value(),
                     },
                     Some(Response::Wait) => State::CheckEntry {
                         current_frame_index,
                         time_since_start: time_since_start + time_step,
+                        cycles_since_start: cycles_since_start + 1,
                     },
                     _ => State::InterpolateSpline {
                         current_frame_index,
@@ -149,6 +226,7 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
                     State::CheckExit {
                         current_frame_index,
                         time_since_start: Duration::ZERO,
+                        cycles_since_start: 0,
                     }
                 } else {
                     State::InterpolateSpline {
@@ -160,24 +238,36 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
             State::CheckExit {
                 current_frame_index,
                 time_since_start,
+                cycles_since_start,
             } => {
                 let current_frame = &self.frames[current_frame_index];
-                match current_frame.exit_condition.as_ref().map(|condition| {
+                let response = current_frame.exit_condition.as_ref().map(|condition| {
                     condition
-                        .evaluate(condition_input)
+                        .evaluate_in_motion(condition_input, cycles_since_start, self.elapsed)
                         .with_timeout(condition.timeout(time_since_start))
-                }) {
+                });
+                if let Some(response) = response {
+                    self.last_condition_evaluation = Some(ConditionEvaluation {
+                        frame_index: current_frame_index,
+                        kind: ConditionKind::Exit,
+                        response,
+                    });
+                }
+                match response {
                     Some(Response::Abort) => State::Aborted {
                         at_position: self.value(),
                     },
                     Some(Response::Wait) => State::CheckExit {
                         current_frame_index,
                         time_since_start: time_since_start + time_step,
+                        cycles_since_start: cycles_since_start + 1,
                     },
                     _ if current_frame_index < self.frames.len() - 1 => State::CheckEntry {
                         current_frame_index: current_frame_index + 1,
                         time_since_start: Duration::ZERO,
+                        cycles_since_start: 0,
                     },
+                    _ if self.hold_final => State::HoldingFinal,
                     _ => State::Finished,
                 }
             }
@@ -190,15 +280,50 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
             return;
         }
 
+        let was_finished = self.is_finished();
         self.advance_state(time_step, condition_input);
+        if !was_finished {
+            self.elapsed += time_step;
+        }
+    }
+
+    /// Total time the motion advanced since it started, across all frames and including the
+    /// blend. Unlike the time within the current state, it is not reset on state transitions.
+    /// It stays frozen while an interrupt condition pauses the motion and after it finished.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
     }
 
     pub fn is_finished(&self) -> bool {
-        matches!(self.current_state, State::Finished | State::Aborted { .. })
+        matches!(
+            self.current_state,
+            State::HoldingFinal | State::Finished | State::Aborted { .. }
+        )
+    }
+
+    /// When enabled, the interpolator keeps holding the last position after the motion finished
+    /// until [`Self::release_hold`] is called. The setting is kept by
+    /// [`Self::reset_preserving_settings`] and cleared by [`Self::reset`].
+    pub fn set_hold_final(&mut self, hold_final: bool) {
+        self.hold_final = hold_final;
+    }
+
+    pub fn is_holding_final(&self) -> bool {
+        matches!(self.current_state, State::HoldingFinal)
+    }
+
+    pub fn release_hold(&mut self) {
+        if let State::HoldingFinal = self.current_state {
+            self.current_state = State::Finished;
+        }
     }
 
     pub fn value(&self) -> T {
         match self.current_state {
+            State::Blend { time_since_start } => match &self.blend {
+                Some(blend) => blend.value_at(time_since_start),
+                None => self.frames[0].spline.start_position(),
+            },
             State::CheckEntry {
                 current_frame_index,
                 ..
@@ -213,16 +338,109 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
                 current_frame_index,
                 ..
             } => self.frames[current_frame_index].spline.end_position(),
-            State::Finished => self.frames.last().unwrap().spline.end_position(),
+            State::HoldingFinal | State::Finished => {
+                self.frames.last().unwrap().spline.end_position()
+            }
             State::Aborted { at_position } => at_position,
         }
     }
 
+    /// The entry or exit condition the interpolator is currently waiting on, if any.
+    pub fn waiting_condition(&self) -> Option<&DiscreteConditionType> {
+        match self.current_state {
+            State::CheckEntry {
+                current_frame_index,
+                ..
+            } => self.frames[current_frame_index].entry_condition.as_ref(),
+            State::CheckExit {
+                current_frame_index,
+                ..
+            } => self.frames[current_frame_index].exit_condition.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The condition evaluation that aborted the motion, `None` unless the motion was aborted.
+    /// Nodes use this to tell an aborted motion apart from a finished one, e.g. to relax joints.
+    pub fn abort_reason(&self) -> Option<ConditionEvaluation> {
+        self.last_condition_evaluation
+            .filter(|_| self.current_state.is_aborted())
+    }
+
+    /// Adds `condition` to the interrupt conditions of every frame, e.g. a safety abort that
+    /// should apply to a motion regardless of its motion file.
+    pub fn add_interrupt_condition(&mut self, condition: ContinuousConditionType) {
+        for frame in &mut self.frames {
+            frame.interrupt_conditions.push(condition.clone());
+        }
+        self.interrupt_conditions.push(condition);
+    }
+
+    /// The most recently evaluated condition and its response, including timeouts. It is kept
+    /// until another condition is evaluated or the motion is reset, so it also explains why a
+    /// finished motion aborted.
+    pub fn last_condition_evaluation(&self) -> Option<ConditionEvaluation> {
+        self.last_condition_evaluation
+    }
+
+    /// Restarts the motion from its first frame and discards everything configured for the
+    /// previous run, i.e. a blend set up by [`Self::start_from_current`], frame time scales and
+    /// holding the final position.
     pub fn reset(&mut self) {
+        self.blend = None;
+        self.hold_final = false;
+        for (frame, factor) in self.frames.iter_mut().zip(&self.frame_time_scales) {
+            frame.spline.scale_time(1.0 / factor);
+        }
+        self.frame_time_scales.clear();
+        self.reset_preserving_settings();
+    }
+
+    /// Restarts the motion like [`Self::reset`], but keeps the playback configuration, i.e. a
+    /// configured blend is replayed before the first frame.
+    pub fn reset_preserving_settings(&mut self) {
+        self.reset_conditions();
+        self.last_condition_evaluation = None;
+        self.elapsed = Duration::ZERO;
+        self.current_state = match self.blend {
+            Some(_) => State::Blend {
+                time_since_start: Duration::ZERO,
+            },
+            None => State::CheckEntry {
+                current_frame_index: 0,
+                time_since_start: Duration::ZERO,
+                cycles_since_start: 0,
+            },
+        };
+    }
+
+    pub fn reset_to_frame(&mut self, frame_index: usize) -> Result<()> {
+        if frame_index >= self.frames.len() {
+            bail!(
+                "cannot reset to frame {frame_index}, motion only has {} frames",
+                self.frames.len()
+            );
+        }
         self.current_state = State::CheckEntry {
-            current_frame_index: 0,
+            current_frame_index: frame_index,
             time_since_start: Duration::ZERO,
+            cycles_since_start: 0,
         };
+        self.reset_conditions();
+        self.last_condition_evaluation = None;
+        self.elapsed = Duration::ZERO;
+        Ok(())
+    }
+
+    fn reset_conditions(&self) {
+        for frame in &self.frames {
+            for condition in frame.entry_condition.iter().chain(&frame.exit_condition) {
+                condition.reset();
+            }
+            for condition in &frame.interrupt_conditions {
+                condition.reset();
+            }
+        }
     }
 
     pub fn set_initial_positions(&mut self, position: T) {
@@ -231,7 +449,120 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
         }
     }
 
+    /// Blends from `current` into the start of the first frame over `blend_duration` before the
+    /// motion is executed, instead of jumping to the motion's assumed initial positions.
+    pub fn start_from_current(&mut self, current: T, blend_duration: Duration) -> Result<()> {
+        let Some(first_frame) = self.frames.first() else {
+            bail!("cannot blend into a motion without frames");
+        };
+        self.blend = Some(TimedSpline::try_new_transition_timed(
+            current,
+            first_frame.spline.start_position(),
+            blend_duration,
+        )?);
+        self.current_state = State::Blend {
+            time_since_start: Duration::ZERO,
+        };
+        self.reset_conditions();
+        self.elapsed = Duration::ZERO;
+        Ok(())
+    }
+
+    /// Extends this motion by the frames of `other`, connected by a spline from the end of this
+    /// motion to the start of `other` over `connection_duration`. A finished motion continues
+    /// with the connection, so it only finishes at the end of the combined motion. Interrupt
+    /// conditions added to this motion also apply to the connection and the appended frames.
+    pub fn append(&mut self, other: Self, connection_duration: Duration) -> Result<()> {
+        let (Some(last_frame), Some(first_appended_frame)) =
+            (self.frames.last(), other.frames.first())
+        else {
+            bail!("cannot append motions without frames");
+        };
+        let connection = ConditionedSpline {
+            entry_condition: None,
+            interrupt_conditions: self.interrupt_conditions.clone(),
+            spline: TimedSpline::try_new_transition_timed(
+                last_frame.spline.end_position(),
+                first_appended_frame.spline.start_position(),
+                connection_duration,
+            )?,
+            exit_condition: None,
+        };
+        let connection_index = self.frames.len();
+        self.frames.push(connection);
+        self.frames
+            .extend(other.frames.into_iter().map(|mut frame| {
+                frame
+                    .interrupt_conditions
+                    .extend(self.interrupt_conditions.iter().cloned());
+                frame
+            }));
+        if let State::HoldingFinal | State::Finished = self.current_state {
+            self.current_state = State::CheckEntry {
+                current_frame_index: connection_index,
+                time_since_start: Duration::ZERO,
+                cycles_since_start: 0,
+            };
+        }
+        Ok(())
+    }
+
+    /// Scales the duration of every frame by the corresponding entry of `factors`, e.g. to slow
+    /// a stand-up motion down on slippery ground. The factors replace the ones of previous calls
+    /// instead of accumulating. They are kept by [`Self::reset_preserving_settings`] and undone
+    /// by [`Self::reset`].
+    pub fn set_frame_time_scale(&mut self, factors: &[f32]) -> Result<()> {
+        if factors.len() != self.frames.len() {
+            bail!(
+                "got {} time scale factors for a motion with {} frames",
+                factors.len(),
+                self.frames.len()
+            );
+        }
+        if let Some(factor) = factors
+            .iter()
+            .find(|factor| !factor.is_finite() || **factor <= 0.0)
+        {
+            bail!("time scale factor {factor} is not positive");
+        }
+        for (index, (frame, factor)) in self.frames.iter_mut().zip(factors).enumerate() {
+            let previous_factor = self.frame_time_scales.get(index).copied().unwrap_or(1.0);
+            frame.spline.scale_time(factor / previous_factor);
+        }
+        self.frame_time_scales = factors.to_vec();
+        Ok(())
+    }
+
+    /// Sum of the spline durations of all frames. Time spent waiting on entry or exit conditions
+    /// depends on the robot's state and is therefore not included.
+    pub fn total_duration(&self) -> Duration {
+        self.frames
+            .iter()
+            .map(|frame| frame.spline.total_duration())
+            .sum()
+    }
+
+    /// Fraction of the motion's total duration that has already been executed, ranging from 0.0
+    /// to 1.0. Time spent waiting on conditions does not count as progress.
+    pub fn progress(&self) -> f32 {
+        let total_duration = self.total_duration();
+        if let State::HoldingFinal | State::Finished = self.current_state {
+            return 1.0;
+        }
+        if total_duration.is_zero() {
+            return 0.0;
+        }
+        let remaining_duration = self.estimated_remaining_duration().min(total_duration);
+        1.0 - remaining_duration.as_secs_f32() / total_duration.as_secs_f32()
+    }
+
     pub fn estimated_remaining_duration(&self) -> Duration {
+        if let State::Blend { time_since_start } = self.current_state {
+            let remaining_blend = self.blend.as_ref().map_or(Duration::ZERO, |blend| {
+                blend.total_duration().saturating_sub(time_since_start)
+            });
+            return remaining_blend + self.total_duration();
+        }
         match self.current_state.current_frame_index() {
             Some(index) => {
                 let mut remaining = self
@@ -248,8 +579,8 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
                         self.frames[index].spline.total_duration(),
                         time_since_start,
                     ),
-                    State::CheckExit { .. } => Duration::ZERO,
-                    State::Finished => Duration::ZERO,
+                    State::Blend { .. } | State::CheckExit { .. } => Duration::ZERO,
+                    State::HoldingFinal | State::Finished => Duration::ZERO,
                     State::Aborted { .. } => Duration::MAX,
                 };
                 remaining
@@ -265,13 +596,91 @@ impl<T: Debug + Interpolate<f32>> MotionInterpolator<T> {
     }
 }
 
-impl<T: Debug + Interpolate<f32>> TryFrom<MotionFile<T>> for MotionInterpolator<T> {
+impl<T, S> MotionInterpolator<T, S>
+where
+    T: Debug + Interpolate<S> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    S: SplineScalar,
+{
+    /// Scales the deviation of every keyframe from the start of the motion per joint by the
+    /// corresponding entry of `factors`, e.g. to fit a motion into a smaller range of motion.
+    /// A factor of 1.0 keeps the joint's motion, 0.0 holds the joint at its start position.
+    pub fn scale_joint_amplitudes(&mut self, factors: T) {
+        let Some(first_frame) = self.frames.first() else {
+            return;
+        };
+        let start = first_frame.spline.start_position();
+        for frame in &mut self.frames {
+            frame
+                .spline
+                .map_positions(|position| start + (position - start) * factors);
+        }
+    }
+}
+
+impl<T, S> MotionInterpolator<T, S>
+where
+    T: Debug + Interpolate<S> + Mirror,
+    S: SplineScalar,
+{
+    /// Swaps the left and right side of all positions, e.g. to execute a motion designed for one
+    /// side on the other. Conditions are kept as they are, so they should not depend on a side.
+    pub fn mirrored(mut self) -> Self {
+        for frame in &mut self.frames {
+            frame.spline.map_positions(Mirror::mirrored);
+        }
+        if let Some(blend) = &mut self.blend {
+            blend.map_positions(Mirror::mirrored);
+        }
+        if let State::Aborted { at_position } = self.current_state {
+            self.current_state = State::Aborted {
+                at_position: at_position.mirrored(),
+            };
+        }
+        self
+    }
+}
+
+impl<T, S> MotionInterpolator<T, S>
+where
+    T: Debug + Default + Interpolate<S> + Sub<Output = T> + Div<f32, Output = T>,
+    S: SplineScalar,
+{
+    /// Velocity of the interpolated value per second, zero while not interpolating a spline.
+    pub fn velocity(&self) -> T {
+        match self.current_state {
+            State::Blend { time_since_start } => self
+                .blend
+                .as_ref()
+                .map_or_else(T::default, |blend| blend.velocity_at(time_since_start)),
+            State::InterpolateSpline {
+                current_frame_index,
+                time_since_start,
+            } => self.frames[current_frame_index]
+                .spline
+                .velocity_at(time_since_start),
+            _ => T::default(),
+        }
+    }
+}
+
+impl<T: Debug + Interpolate<S>, S: SplineScalar> TryFrom<MotionFile<T>>
+    for MotionInterpolator<T, S>
+{
     type Error = Report;
 
     fn try_from(motion_file: MotionFile<T>) -> Result<Self> {
         let interpolation_mode = motion_file.interpolation_mode;
 
-        let first_frame = motion_file.motion.first().unwrap();
+        let Some(first_frame) = motion_file.motion.first() else {
+            bail!("motion file has no frames");
+        };
+        if let Some(frame_index) = motion_file
+            .motion
+            .iter()
+            .position(|frame| frame.keyframes.is_empty())
+        {
+            bail!("frame {frame_index} of motion file has no keyframes");
+        }
 
         let mut motion_frames = vec![ConditionedSpline {
             entry_condition: first_frame.entry_condition.clone(),
@@ -280,7 +689,8 @@ impl<T: Debug + Interpolate<f32>> TryFrom<MotionFile<T>> for MotionInterpolator<
                 motion_file.initial_positions,
                 first_frame.keyframes.clone(),
                 interpolation_mode,
-            )?,
+            )
+            .wrap_err("failed to create spline of frame 0")?,
             exit_condition: first_frame.exit_condition.clone(),
         }];
 
@@ -289,7 +699,8 @@ impl<T: Debug + Interpolate<f32>> TryFrom<MotionFile<T>> for MotionInterpolator<
                 .motion
                 .into_iter()
                 .tuple_windows()
-                .map(|(first_frame, second_frame)| {
+                .enumerate()
+                .map(|(first_frame_index, (first_frame, second_frame))| {
                     Ok(ConditionedSpline {
                         entry_condition: second_frame.entry_condition,
                         interrupt_conditions: second_frame.interrupt_conditions,
@@ -297,19 +708,569 @@ impl<T: Debug + Interpolate<f32>> TryFrom<MotionFile<T>> for MotionInterpolator<
                             first_frame.keyframes.last().unwrap().positions,
                             second_frame.keyframes,
                             interpolation_mode,
-                        )?,
+                        )
+                        .wrap_err_with(|| {
+                            format!("failed to create spline of frame {}", first_frame_index + 1)
+                        })?,
                         exit_condition: second_frame.exit_condition,
                     })
                 })
-                .collect::<Result<Vec<_>, InterpolatorError>>()?,
+                .collect::<Result<Vec<_>>>()?,
         );
 
         Ok(Self {
             current_state: State::CheckEntry {
                 current_frame_index: 0,
                 time_since_start: Duration::ZERO,
+                cycles_since_start: 0,
             },
             frames: motion_frames,
+            blend: None,
+            interrupt_conditions: Vec::new(),
+            hold_final: false,
+            frame_time_scales: Vec::new(),
+            elapsed: Duration::ZERO,
+            last_condition_evaluation: None,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use splines::Interpolation;
+    use types::joints::Joints;
+
+    use crate::{KeyFrame, CURRENT_MOTION_FILE_VERSION};
+
+    use super::*;
+
+    fn frame(positions: &[f32]) -> crate::MotionFileFrame<f32> {
+        crate::MotionFileFrame {
+            name: None,
+            entry_condition: None,
+            interrupt_conditions: vec![],
+            keyframes: positions
+                .iter()
+                .map(|&position| KeyFrame {
+                    duration: Duration::from_secs(1),
+                    positions: position,
+                })
+                .collect(),
+            exit_condition: None,
+        }
+    }
+
+    fn interpolator(frames: &[&[f32]]) -> MotionInterpolator<f32> {
+        MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: 0.0,
+            motion: frames.iter().map(|positions| frame(positions)).collect(),
+        }
+        .try_into()
+        .unwrap()
+    }
+
+    #[test]
+    fn reset_to_frame_starts_at_given_frame() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0], &[3.0]]);
+
+        interpolator.reset_to_frame(2).unwrap();
+        assert_eq!(interpolator.value(), 2.0);
+
+        let condition_input = ConditionInput::default();
+        for _ in 0..5 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(interpolator.is_finished());
+        assert_eq!(interpolator.value(), 3.0);
+    }
+
+    #[test]
+    fn reset_discards_blend() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator
+            .start_from_current(-1.0, Duration::from_secs(1))
+            .unwrap();
+        let condition_input = ConditionInput::default();
+        for _ in 0..3 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+
+        interpolator.reset();
+
+        assert_eq!(interpolator.value(), 0.0);
+        assert_eq!(
+            interpolator.estimated_remaining_duration(),
+            interpolator.total_duration()
+        );
+    }
+
+    #[test]
+    fn reset_preserving_settings_keeps_playback_configuration() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator
+            .start_from_current(-1.0, Duration::from_secs(1))
+            .unwrap();
+        interpolator.set_hold_final(true);
+        interpolator.set_frame_time_scale(&[2.0, 1.0]).unwrap();
+        let condition_input = ConditionInput::default();
+        for _ in 0..3 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+
+        interpolator.reset_preserving_settings();
+
+        assert_eq!(interpolator.value(), -1.0);
+        assert_eq!(
+            interpolator.estimated_remaining_duration(),
+            Duration::from_secs(4)
+        );
+        for _ in 0..20 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(interpolator.is_holding_final());
+
+        interpolator.reset();
+
+        assert_eq!(interpolator.value(), 0.0);
+        assert_eq!(
+            interpolator.estimated_remaining_duration(),
+            Duration::from_secs(2)
+        );
+        for _ in 0..20 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(interpolator.is_finished());
+        assert!(!interpolator.is_holding_final());
+    }
+
+    #[test]
+    fn hold_final_keeps_last_position_until_released() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator.set_hold_final(true);
+
+        let condition_input = ConditionInput::default();
+        for _ in 0..10 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(interpolator.is_finished());
+        assert!(interpolator.is_holding_final());
+        assert_eq!(interpolator.value(), 2.0);
+        assert_eq!(interpolator.progress(), 1.0);
+
+        interpolator.release_hold();
+        assert!(interpolator.is_finished());
+        assert!(!interpolator.is_holding_final());
+        assert_eq!(interpolator.value(), 2.0);
+    }
+
+    #[test]
+    fn start_from_current_blends_into_first_frame() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+
+        interpolator
+            .start_from_current(-1.0, Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(interpolator.value(), -1.0);
+        assert_eq!(
+            interpolator.estimated_remaining_duration(),
+            Duration::from_secs(3)
+        );
+
+        let condition_input = ConditionInput::default();
+        for _ in 0..3 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert_eq!(interpolator.value(), 0.0);
+    }
+
+    #[test]
+    fn progress_increases_over_motion() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        let condition_input = ConditionInput::default();
+
+        assert_eq!(interpolator.progress(), 0.0);
+        for _ in 0..3 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert_eq!(interpolator.progress(), 0.5);
+        for _ in 0..10 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(interpolator.is_finished());
+        assert_eq!(interpolator.progress(), 1.0);
+    }
+
+    #[test]
+    fn velocity_integrates_to_position_change() {
+        let mut interpolator = interpolator(&[&[1.0], &[-2.0]]);
+        let condition_input = ConditionInput::default();
+        let time_step = Duration::from_millis(10);
+
+        assert_eq!(interpolator.velocity(), 0.0);
+        interpolator.advance_by(time_step, &condition_input);
+
+        let start_position = interpolator.value();
+        let mut integrated_position = start_position;
+        for _ in 0..150 {
+            integrated_position += interpolator.velocity() * time_step.as_secs_f32();
+            interpolator.advance_by(time_step, &condition_input);
+        }
+        assert_ne!(interpolator.value(), start_position);
+        assert!((integrated_position - interpolator.value()).abs() < 0.05);
+    }
+
+    #[test]
+    fn total_duration_sums_all_frames() {
+        let interpolator = interpolator(&[&[1.0, 2.0], &[3.0], &[4.0, 5.0, 6.0]]);
+
+        assert_eq!(interpolator.total_duration(), Duration::from_secs(6));
+    }
+
+    #[test]
+    fn frame_time_scales_change_the_frame_durations() {
+        let mut interpolator = interpolator(&[&[1.0, 2.0], &[3.0]]);
+
+        interpolator.set_frame_time_scale(&[2.0, 0.5]).unwrap();
+        assert_eq!(interpolator.total_duration(), Duration::from_millis(4500));
+
+        interpolator.set_frame_time_scale(&[1.0, 1.0]).unwrap();
+        assert_eq!(interpolator.total_duration(), Duration::from_secs(3));
+
+        assert!(interpolator.set_frame_time_scale(&[1.0]).is_err());
+        assert!(interpolator.set_frame_time_scale(&[1.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn reset_to_frame_rejects_out_of_range_index() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+
+        assert!(interpolator.reset_to_frame(2).is_err());
+        assert_eq!(interpolator.value(), 0.0);
+    }
+
+    #[test]
+    fn last_condition_evaluation_reports_waiting_exit_condition() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator.frames[1].exit_condition = Some(
+            serde_json::from_str(
+                r#"{"GamePhase": {"phase": "Playing", "timeout_duration": 10.0}}"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(interpolator.last_condition_evaluation(), None);
+
+        let condition_input = ConditionInput::default();
+        for _ in 0..10 {
+            interpolator.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(!interpolator.is_finished());
+        assert_eq!(
+            interpolator.last_condition_evaluation(),
+            Some(ConditionEvaluation {
+                frame_index: 1,
+                kind: ConditionKind::Exit,
+                response: Response::Wait,
+            })
+        );
+
+        interpolator.reset();
+        assert_eq!(interpolator.last_condition_evaluation(), None);
+    }
+
+    #[test]
+    fn entry_condition_waits_for_exact_number_of_cycles() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator.frames[1].entry_condition =
+            Some(serde_json::from_str(r#"{"Cycles": 3}"#).unwrap());
+        let condition_input = ConditionInput::default();
+
+        // Run through the first frame until the entry condition of the second one is checked.
+        while interpolator.waiting_condition().is_none() {
+            interpolator.advance_by(Duration::from_millis(100), &condition_input);
+        }
+        // Waits for three cycles regardless of their duration and continues in the fourth.
+        for _ in 0..3 {
+            interpolator.advance_by(Duration::from_secs(10), &condition_input);
+            assert!(interpolator.waiting_condition().is_some());
+            assert_eq!(interpolator.value(), 1.0);
+        }
+
+        interpolator.advance_by(Duration::from_millis(100), &condition_input);
+        assert!(interpolator.waiting_condition().is_none());
+        assert_eq!(
+            interpolator.last_condition_evaluation(),
+            Some(ConditionEvaluation {
+                frame_index: 1,
+                kind: ConditionKind::Entry,
+                response: Response::Continue,
+            })
+        );
+    }
+
+    #[test]
+    fn elapsed_time_accumulates_across_states_until_finished() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator
+            .start_from_current(0.0, Duration::from_secs(1))
+            .unwrap();
+        interpolator.frames[1].entry_condition =
+            Some(serde_json::from_str(r#"{"Cycles": 4}"#).unwrap());
+        let condition_input = ConditionInput::default();
+        let time_step = Duration::from_millis(500);
+
+        let mut cycles = 0;
+        while !interpolator.is_finished() {
+            interpolator.advance_by(time_step, &condition_input);
+            cycles += 1;
+            assert!(interpolator.elapsed() <= time_step * cycles);
+        }
+        let elapsed_when_finished = interpolator.elapsed();
+        assert_eq!(elapsed_when_finished, time_step * cycles);
+
+        for _ in 0..3 {
+            interpolator.advance_by(time_step, &condition_input);
+        }
+        assert_eq!(interpolator.elapsed(), elapsed_when_finished);
+
+        interpolator.reset();
+        assert_eq!(interpolator.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn total_elapsed_condition_is_measured_from_motion_start() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator.frames[0].exit_condition =
+            Some(serde_json::from_str(r#"{"TotalElapsed": 2.5}"#).unwrap());
+        let condition_input = ConditionInput::default();
+        let time_step = Duration::from_millis(500);
+
+        while interpolator.waiting_condition().is_none() {
+            interpolator.advance_by(time_step, &condition_input);
+        }
+        // The frame ended after one second but waits until the motion ran for 2.5 seconds.
+        while interpolator.waiting_condition().is_some() {
+            assert!(interpolator.elapsed() < Duration::from_millis(3000));
+            assert_eq!(interpolator.value(), 1.0);
+            interpolator.advance_by(time_step, &condition_input);
+        }
+        assert_eq!(interpolator.elapsed(), Duration::from_millis(3000));
+        assert_eq!(
+            interpolator.last_condition_evaluation(),
+            Some(ConditionEvaluation {
+                frame_index: 0,
+                kind: ConditionKind::Exit,
+                response: Response::Continue,
+            })
+        );
+    }
+
+    #[test]
+    fn scaling_joint_amplitudes_around_start() {
+        let mut unscaled = interpolator(&[&[1.0, -2.0], &[3.0]]);
+        let sample = |motion: &mut MotionInterpolator<f32>| {
+            motion.reset();
+            let condition_input = ConditionInput::default();
+            (0..12)
+                .map(|_| {
+                    motion.advance_by(Duration::from_millis(300), &condition_input);
+                    motion.value()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut identity = interpolator(&[&[1.0, -2.0], &[3.0]]);
+        identity.scale_joint_amplitudes(1.0);
+        let mut held = interpolator(&[&[1.0, -2.0], &[3.0]]);
+        held.scale_joint_amplitudes(0.0);
+        let mut halved = interpolator(&[&[1.0, -2.0], &[3.0]]);
+        halved.scale_joint_amplitudes(0.5);
+
+        let expected = sample(&mut unscaled);
+        assert_eq!(sample(&mut identity), expected);
+        assert!(sample(&mut held).iter().all(|&position| position == 0.0));
+        for (halved, expected) in sample(&mut halved).into_iter().zip(expected) {
+            assert!((halved - expected / 2.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn try_from_rejects_motion_without_frames() {
+        let motion_file = MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: 0.0,
+            motion: vec![],
+        };
+
+        let error = MotionInterpolator::<f32>::try_from(motion_file).unwrap_err();
+        assert_eq!(error.to_string(), "motion file has no frames");
+    }
+
+    #[test]
+    fn mirrored_motion_swaps_sides_of_all_keyframes() {
+        let mut start = Joints::default();
+        start.left_arm.shoulder_roll = 0.5;
+        let mut end = Joints::default();
+        end.right_leg.hip_roll = -0.2;
+        end.right_leg.knee_pitch = 1.0;
+        let motion_file = MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: start,
+            motion: vec![crate::MotionFileFrame {
+                name: None,
+                entry_condition: None,
+                interrupt_conditions: vec![],
+                keyframes: vec![KeyFrame {
+                    duration: Duration::from_secs(1),
+                    positions: end,
+                }],
+                exit_condition: None,
+            }],
+        };
+        let original = MotionInterpolator::<Joints<f32>>::try_from(motion_file.clone()).unwrap();
+        let mut mirrored = MotionInterpolator::<Joints<f32>>::try_from(motion_file)
+            .unwrap()
+            .mirrored();
+
+        assert_eq!(mirrored.value(), original.value().mirrored());
+        assert_eq!(mirrored.value().right_arm.shoulder_roll, -0.5);
+
+        let condition_input = ConditionInput::default();
+        for _ in 0..5 {
+            mirrored.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(mirrored.is_finished());
+        assert_eq!(mirrored.value(), end.mirrored());
+        assert_eq!(mirrored.value().left_leg.knee_pitch, 1.0);
+    }
+
+    #[test]
+    fn injected_interrupt_condition_aborts_and_reports_reason() {
+        let mut interpolator = interpolator(&[&[1.0], &[2.0]]);
+        interpolator.add_interrupt_condition(crate::ExcessiveAcceleration::new(5.0).into());
+        let resting = ConditionInput {
+            linear_acceleration: nalgebra::Vector3::new(0.0, 0.0, 9.81),
+            ..Default::default()
+        };
+        let pushed = ConditionInput {
+            linear_acceleration: nalgebra::Vector3::new(20.0, 0.0, 9.81),
+            ..Default::default()
+        };
+
+        interpolator.advance_by(Duration::from_millis(500), &resting);
+        interpolator.advance_by(Duration::from_millis(500), &resting);
+        assert_eq!(interpolator.abort_reason(), None);
+
+        interpolator.advance_by(Duration::from_millis(500), &pushed);
+        assert!(interpolator.is_finished());
+        assert_eq!(
+            interpolator.abort_reason(),
+            Some(ConditionEvaluation {
+                frame_index: 0,
+                kind: ConditionKind::Interrupt,
+                response: Response::Abort,
+            })
+        );
+
+        interpolator.reset();
+        assert_eq!(interpolator.abort_reason(), None);
+    }
+
+    #[test]
+    fn injected_interrupt_condition_aborts_blend_and_appended_frames() {
+        let pushed = ConditionInput {
+            linear_acceleration: nalgebra::Vector3::new(20.0, 0.0, 9.81),
+            ..Default::default()
+        };
+
+        let mut blended = interpolator(&[&[1.0]]);
+        blended.add_interrupt_condition(crate::ExcessiveAcceleration::new(5.0).into());
+        blended
+            .start_from_current(-1.0, Duration::from_secs(1))
+            .unwrap();
+        blended.advance_by(Duration::from_millis(500), &pushed);
+        assert!(blended.abort_reason().is_some());
+
+        let mut appended = interpolator(&[&[1.0]]);
+        appended.add_interrupt_condition(crate::ExcessiveAcceleration::new(5.0).into());
+        appended
+            .append(interpolator(&[&[2.0]]), Duration::from_secs(1))
+            .unwrap();
+        assert!(appended.frames[1..]
+            .iter()
+            .all(|frame| !frame.interrupt_conditions.is_empty()));
+    }
+
+    #[test]
+    fn try_from_names_the_frame_of_spline_errors() {
+        let motion_file = MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Interpolation::Bezier(0.5),
+            initial_positions: 0.0,
+            motion: vec![frame(&[1.0])],
+        };
+
+        let error = MotionInterpolator::<f32>::try_from(motion_file).unwrap_err();
+
+        assert_eq!(error.to_string(), "failed to create spline of frame 0");
+        assert!(format!("{error:?}").contains("unsupported interpolation mode"));
+    }
+
+    #[test]
+    fn try_from_rejects_frame_without_keyframes() {
+        let motion_file = MotionFile {
+            version: CURRENT_MOTION_FILE_VERSION,
+            interpolation_mode: Default::default(),
+            initial_positions: 0.0,
+            motion: vec![frame(&[1.0]), frame(&[]), frame(&[2.0])],
+        };
+
+        let error = MotionInterpolator::<f32>::try_from(motion_file).unwrap_err();
+        assert_eq!(error.to_string(), "frame 1 of motion file has no keyframes");
+    }
+
+    #[test]
+    fn append_continues_smoothly_into_other_motion() {
+        let mut motion = interpolator(&[&[1.0], &[2.0]]);
+        let mut other = interpolator(&[&[4.0], &[3.0]]);
+        other.set_initial_positions(3.0);
+        motion.append(other, Duration::from_secs(1)).unwrap();
+        assert_eq!(motion.total_duration(), Duration::from_secs(5));
+
+        let condition_input = ConditionInput::default();
+        let time_step = Duration::from_millis(10);
+        let mut previous_value = motion.value();
+        let mut steps = 0;
+        while !motion.is_finished() {
+            motion.advance_by(time_step, &condition_input);
+            let value = motion.value();
+            assert!(
+                (value - previous_value).abs() < 0.1,
+                "jump from {previous_value} to {value}"
+            );
+            previous_value = value;
+            steps += 1;
+        }
+        assert!(steps >= 500);
+        assert_eq!(motion.value(), 3.0);
+    }
+
+    #[test]
+    fn append_to_finished_motion_continues_with_appended_frames() {
+        let mut motion = interpolator(&[&[1.0]]);
+        let condition_input = ConditionInput::default();
+        for _ in 0..5 {
+            motion.advance_by(Duration::from_millis(500), &condition_input);
+        }
+        assert!(motion.is_finished());
+
+        motion
+            .append(interpolator(&[&[2.0]]), Duration::from_secs(1))
+            .unwrap();
+        assert!(!motion.is_finished());
+        assert_eq!(motion.value(), 1.0);
+    }
+}
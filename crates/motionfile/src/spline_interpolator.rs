@@ -4,15 +4,15 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use splines::Interpolate;
 
-use crate::TimedSpline;
+use crate::{timed_spline::SplineScalar, TimedSpline};
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct SplineInterpolator<T> {
-    spline: TimedSpline<T>,
+pub struct SplineInterpolator<T, S = f32> {
+    spline: TimedSpline<T, S>,
     current_duration: Duration,
 }
 
-impl<T: Debug + Interpolate<f32>> SplineInterpolator<T> {
+impl<T: Debug + Interpolate<S>, S: SplineScalar> SplineInterpolator<T, S> {
     pub fn advance_by(&mut self, time_step: Duration) {
         self.current_duration += time_step
     }
@@ -38,8 +38,8 @@ impl<T: Debug + Interpolate<f32>> SplineInterpolator<T> {
     }
 }
 
-impl<T> From<TimedSpline<T>> for SplineInterpolator<T> {
-    fn from(spline: TimedSpline<T>) -> Self {
+impl<T, S> From<TimedSpline<T, S>> for SplineInterpolator<T, S> {
+    fn from(spline: TimedSpline<T, S>) -> Self {
         Self {
             spline,
             current_duration: Duration::ZERO,
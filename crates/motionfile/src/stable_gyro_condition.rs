@@ -0,0 +1,87 @@
+use std::{cell::RefCell, fmt::Debug, time::Duration};
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use filtering::low_pass_filter::LowPassFilter;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use types::condition_input::ConditionInput;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StableGyro {
+    tolerance: f32,
+    coefficient: f32,
+    #[serde(with = "crate::serde_seconds")]
+    timeout_duration: Duration,
+    #[serde(skip)]
+    angular_velocity_filter: RefCell<Option<LowPassFilter<Vector3<f32>>>>,
+}
+
+impl Condition for StableGyro {
+    fn evaluate(&self, condition_input: &ConditionInput) -> Response {
+        let mut angular_velocity_filter = self.angular_velocity_filter.borrow_mut();
+        let angular_velocity_filter = angular_velocity_filter.get_or_insert_with(|| {
+            LowPassFilter::with_smoothing_factor(condition_input.angular_velocity, self.coefficient)
+        });
+        angular_velocity_filter.update(condition_input.angular_velocity);
+
+        if angular_velocity_filter.state().norm() < self.tolerance {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+
+    fn reset(&self) {
+        self.angular_velocity_filter.replace(None);
+    }
+}
+
+impl TimeOut for StableGyro {
+    fn timeout(&self, time_since_start: Duration) -> bool {
+        time_since_start > self.timeout_duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_until_filtered_gyro_converges() {
+        let condition: StableGyro = serde_json::from_str(
+            r#"{ "tolerance": 0.01, "coefficient": 0.2, "timeout_duration": 1.0 }"#,
+        )
+        .unwrap();
+        let shaking = ConditionInput {
+            angular_velocity: Vector3::new(1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        let resting = ConditionInput::default();
+
+        assert!(matches!(condition.evaluate(&shaking), Response::Wait));
+        assert!(matches!(condition.evaluate(&resting), Response::Wait));
+
+        let cycles_until_stable = (0..100)
+            .position(|_| matches!(condition.evaluate(&resting), Response::Continue))
+            .expect("filtered gyro did not converge");
+        assert!(cycles_until_stable > 0);
+    }
+
+    #[test]
+    fn reset_forgets_filtered_gyro() {
+        let condition: StableGyro = serde_json::from_str(
+            r#"{ "tolerance": 0.01, "coefficient": 0.2, "timeout_duration": 1.0 }"#,
+        )
+        .unwrap();
+        let shaking = ConditionInput {
+            angular_velocity: Vector3::new(1.0, 0.0, 0.0),
+            ..Default::default()
+        };
+        let resting = ConditionInput::default();
+
+        assert!(matches!(condition.evaluate(&shaking), Response::Wait));
+        condition.reset();
+
+        assert!(matches!(condition.evaluate(&resting), Response::Continue));
+    }
+}
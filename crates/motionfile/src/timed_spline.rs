@@ -1,30 +1,82 @@
 use crate::KeyFrame;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use splines::{Interpolate, Interpolation, Key, Spline};
+use splines::{interpolate::Interpolator, Interpolate, Interpolation, Key, Spline};
 use thiserror::Error;
 use types::{joints::Joints, joints_velocity::JointsVelocity};
 
-use std::{fmt::Debug, time::Duration};
+use std::{
+    fmt::Debug,
+    ops::{Add, Div, Sub},
+    time::Duration,
+};
+
+/// Scalar type of the spline's time axis, in which the spline is sampled and interpolated.
+pub trait SplineScalar: Debug + Interpolator + Add<Output = Self> + Sub<Output = Self> {
+    const ZERO: Self;
+    const EPSILON: Self;
+
+    fn from_duration(duration: Duration) -> Self;
+    fn scaled(self, factor: f32) -> Self;
+}
+
+impl SplineScalar for f32 {
+    const ZERO: Self = 0.0;
+    const EPSILON: Self = f32::EPSILON;
+
+    fn from_duration(duration: Duration) -> Self {
+        duration.as_secs_f32()
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor
+    }
+}
+
+impl SplineScalar for f64 {
+    const ZERO: Self = 0.0;
+    const EPSILON: Self = f64::EPSILON;
+
+    fn from_duration(duration: Duration) -> Self {
+        duration.as_secs_f64()
+    }
+
+    fn scaled(self, factor: f32) -> Self {
+        self * factor as f64
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct TimedSpline<T> {
-    spline: Spline<f32, T>,
+pub struct TimedSpline<T, S = f32> {
+    spline: Spline<S, T>,
     total_duration: Duration,
 }
 
-impl<T> Default for TimedSpline<T>
+impl<T, S> Default for TimedSpline<T, S>
 where
     T: Default,
+    S: Default,
 {
     fn default() -> Self {
         Self {
-            spline: Spline::from_vec(vec![]),
+            spline: Spline::default(),
             total_duration: Duration::ZERO,
         }
     }
 }
 
+/// Names of the interpolation modes motion files may use, as written in the files.
+pub const SUPPORTED_INTERPOLATION_MODES: [&str; 3] = ["linear", "cosine", "catmull_rom"];
+
+pub fn is_supported_interpolation_mode<Argument, Value>(
+    interpolation_mode: &Interpolation<Argument, Value>,
+) -> bool {
+    matches!(
+        interpolation_mode,
+        Interpolation::Linear | Interpolation::Cosine | Interpolation::CatmullRom
+    )
+}
+
 pub trait MapArgumentExt<FromArgument, ToArgument, Value> {
     fn map_argument(self) -> Result<Interpolation<ToArgument, Value>, InterpolatorError>;
 }
@@ -56,18 +108,22 @@ pub enum InterpolatorError {
     NotEnoughKeys,
     #[error("uses unsupported interpolation mode {interpolation_mode}")]
     UnsupportedInterpolationMode { interpolation_mode: String },
-    #[error("the time value is not monotonically increasing")]
-    KeysTimeIncorrect,
+    #[error("key {key_index} at {time:?} is before the previous key at {previous_time:?}")]
+    KeysTimeIncorrect {
+        key_index: usize,
+        time: Duration,
+        previous_time: Duration,
+    },
 }
 
 impl InterpolatorError {
-    fn create_control_key_error<T: Debug>(
-        keys: &[Key<f32, T>],
+    fn create_control_key_error<T: Debug, S: SplineScalar>(
+        keys: &[Key<S, T>],
         current_time: Duration,
     ) -> InterpolatorError {
         let current_control_key = keys
             .iter()
-            .filter(|key| key.t <= current_time.as_secs_f32())
+            .filter(|key| key.t <= S::from_duration(current_time))
             .last()
             .unwrap();
 
@@ -102,9 +158,10 @@ impl TimedSpline<Joints<f32>> {
     }
 }
 
-impl<T> TimedSpline<T>
+impl<T, S> TimedSpline<T, S>
 where
-    T: Debug + Interpolate<f32>,
+    T: Debug + Interpolate<S>,
+    S: SplineScalar,
 {
     pub fn try_new_with_start(
         initial_position: T,
@@ -139,12 +196,17 @@ where
             return Err(InterpolatorError::NotEnoughKeys);
         }
 
-        if keys
+        if let Some((key_index, (previous_key, key))) = keys
             .iter()
             .tuple_windows()
-            .any(|(first_frame, second_frame)| first_frame.t > second_frame.t)
+            .enumerate()
+            .find(|(_, (previous_key, key))| previous_key.t > key.t)
         {
-            return Err(InterpolatorError::KeysTimeIncorrect);
+            return Err(InterpolatorError::KeysTimeIncorrect {
+                key_index: key_index + 1,
+                time: key.t,
+                previous_time: previous_key.t,
+            });
         }
 
         let start_time = keys.first().unwrap().t;
@@ -155,7 +217,7 @@ where
             keys.into_iter()
                 .map(|key| {
                     Ok(Key::new(
-                        key.t.as_secs_f32() - start_time.as_secs_f32(),
+                        S::from_duration(key.t) - S::from_duration(start_time),
                         key.value,
                         key.interpolation.map_argument()?,
                     ))
@@ -182,7 +244,7 @@ where
         current_position: T,
         target_position: T,
         duration: Duration,
-    ) -> Result<TimedSpline<T>, InterpolatorError> {
+    ) -> Result<Self, InterpolatorError> {
         let keys = vec![
             Key::new(Duration::ZERO, current_position, Interpolation::CatmullRom),
             Key::new(duration, target_position, Interpolation::CatmullRom),
@@ -191,9 +253,9 @@ where
         Self::try_new(keys)
     }
 
-    fn create_zero_gradient(key_center: &Key<f32, T>, key_other: &Key<f32, T>) -> Key<f32, T> {
+    fn create_zero_gradient(key_center: &Key<S, T>, key_other: &Key<S, T>) -> Key<S, T> {
         Key::new(
-            2. * key_center.t - key_other.t,
+            key_center.t + key_center.t - key_other.t,
             key_other.value,
             key_center.interpolation,
         )
@@ -203,11 +265,14 @@ where
         if time_point >= self.total_duration {
             return self.end_position();
         }
-        // Duration and f32 have different precisions, we have to ensure that if self.current_duration < self.total_duration, that
-        // self.current_duration.as_secs_f32() != self.total_duration.as_secs_f32(), since otherwise we are unable to sample the spline.
-        let clamped_time_point = time_point
-            .as_secs_f32()
-            .clamp(0., self.total_duration.as_secs_f32() - f32::EPSILON);
+        // Duration and S have different precisions, we have to ensure that if self.current_duration < self.total_duration, that
+        // S::from_duration(self.current_duration) != S::from_duration(self.total_duration), since otherwise we are unable to sample the spline.
+        let latest_time_point = S::from_duration(self.total_duration) - S::EPSILON;
+        let clamped_time_point = match S::from_duration(time_point) {
+            time_point if time_point > latest_time_point => latest_time_point,
+            time_point if time_point < S::ZERO => S::ZERO,
+            time_point => time_point,
+        };
         self.spline
             .sample(clamped_time_point)
             .ok_or_else(|| {
@@ -220,6 +285,18 @@ where
         self.total_duration
     }
 
+    /// `number_of_samples` samples evenly spaced from the start to the end of the spline, e.g. for
+    /// exporting or plotting a motion. A single sample is taken at the start.
+    pub fn sample_uniform(&self, number_of_samples: usize) -> Vec<(Duration, T)> {
+        let number_of_intervals = number_of_samples.saturating_sub(1).max(1) as u32;
+        (0..number_of_samples as u32)
+            .map(|index| {
+                let time_point = self.total_duration * index / number_of_intervals;
+                (time_point, self.value_at(time_point))
+            })
+            .collect()
+    }
+
     // TODO: uses weird indexing due to the artificial keys added in the try_new function
     // if possible in the future, use spline boundary conditions instead.
     pub fn start_position(&self) -> T {
@@ -230,9 +307,182 @@ where
         self.spline.keys()[self.spline.keys().len() - 2].value
     }
 
+    /// Applies `map` to the positions of all keys, including the artificial ones.
+    pub fn map_positions(&mut self, map: impl Fn(T) -> T) {
+        for index in 0..self.spline.len() {
+            if let Some(key) = self.spline.get_mut(index) {
+                *key.value = map(*key.value);
+            }
+        }
+    }
+
     pub fn set_initial_positions(&mut self, position: T) {
         if let Some(key) = self.spline.get_mut(1) {
             *key.value = position;
         }
     }
+
+    /// Stretches the spline in time by `factor`, e.g. a factor of 2.0 takes twice as long.
+    pub fn scale_time(&mut self, factor: f32) {
+        self.spline = Spline::from_vec(
+            self.spline
+                .keys()
+                .iter()
+                .map(|key| Key::new(key.t.scaled(factor), key.value, key.interpolation))
+                .collect(),
+        );
+        self.total_duration = self.total_duration.mul_f32(factor);
+    }
+}
+
+impl<T, S> TimedSpline<T, S>
+where
+    T: Debug + Default + Interpolate<S> + Sub<Output = T> + Div<f32, Output = T>,
+    S: SplineScalar,
+{
+    /// Approximates the derivative of the spline by a central difference around `time_point`. A
+    /// spline without duration does not move.
+    pub fn velocity_at(&self, time_point: Duration) -> T {
+        const TIME_DIFFERENCE: Duration = Duration::from_millis(1);
+
+        let earlier = time_point.saturating_sub(TIME_DIFFERENCE);
+        let later = (time_point + TIME_DIFFERENCE).min(self.total_duration);
+        let elapsed = later.saturating_sub(earlier).as_secs_f32();
+        if elapsed == 0.0 {
+            return T::default();
+        }
+        (self.value_at(later) - self.value_at(earlier)) / elapsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn f64_spline_matches_f32_spline() {
+        let duration = Duration::from_secs(2);
+        let spline_f32 = TimedSpline::<f32>::try_new_transition_timed(1.0, 3.0, duration).unwrap();
+        let spline_f64 =
+            TimedSpline::<f64, f64>::try_new_transition_timed(1.0, 3.0, duration).unwrap();
+
+        assert_eq!(spline_f64.start_position(), 1.0);
+        assert_eq!(spline_f64.value_at(Duration::ZERO), 1.0);
+        assert_eq!(spline_f64.value_at(duration), 3.0);
+        assert!((spline_f64.value_at(Duration::from_secs(1)) - 2.0).abs() < 1e-9);
+        for milliseconds in (0..2000).step_by(100) {
+            let time_point = Duration::from_millis(milliseconds);
+            let difference =
+                spline_f64.value_at(time_point) - spline_f32.value_at(time_point) as f64;
+            assert!(difference.abs() < 1e-5, "{time_point:?}: {difference}");
+        }
+    }
+
+    #[test]
+    fn scaled_spline_reaches_its_positions_later() {
+        let mut spline =
+            TimedSpline::<f32>::try_new_transition_timed(1.0, 3.0, Duration::from_secs(1)).unwrap();
+
+        spline.scale_time(2.0);
+
+        assert_eq!(spline.total_duration(), Duration::from_secs(2));
+        assert!((spline.value_at(Duration::from_secs(1)) - 2.0).abs() < 1e-5);
+        assert_eq!(spline.value_at(Duration::from_secs(2)), 3.0);
+    }
+
+    #[test]
+    fn zero_duration_spline_has_no_velocity() {
+        let spline =
+            TimedSpline::<f32>::try_new_transition_timed(1.0, 3.0, Duration::ZERO).unwrap();
+
+        assert_eq!(spline.velocity_at(Duration::ZERO), 0.0);
+    }
+
+    #[test]
+    fn out_of_order_keys_are_reported_with_their_times() {
+        let keys = [0, 2, 1]
+            .map(|seconds| Key::new(Duration::from_secs(seconds), 0.0, Interpolation::Linear));
+
+        let error = TimedSpline::<f32>::try_new(keys.to_vec()).unwrap_err();
+
+        assert!(matches!(
+            error,
+            InterpolatorError::KeysTimeIncorrect {
+                key_index: 2,
+                time,
+                previous_time,
+            } if time == Duration::from_secs(1) && previous_time == Duration::from_secs(2)
+        ));
+    }
+
+    #[test]
+    fn zero_duration_keyframes_jump_to_their_positions() {
+        let keyframes = vec![
+            KeyFrame {
+                duration: Duration::ZERO,
+                positions: 1.0,
+            },
+            KeyFrame {
+                duration: Duration::from_secs(1),
+                positions: 2.0,
+            },
+        ];
+
+        let spline =
+            TimedSpline::<f32>::try_new_with_start(0.0, keyframes, Interpolation::Linear).unwrap();
+
+        assert_eq!(spline.total_duration(), Duration::from_secs(1));
+        assert!((spline.value_at(Duration::from_millis(500)) - 1.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn supported_interpolation_modes_are_exactly_those_splines_can_be_built_with() {
+        // `Interpolation` is non-exhaustive, so this list has to be extended when upgrading
+        // `splines` adds a variant.
+        let all_modes: [Interpolation<Duration, f32>; 6] = [
+            Interpolation::Step(Duration::from_millis(500)),
+            Interpolation::Linear,
+            Interpolation::Cosine,
+            Interpolation::CatmullRom,
+            Interpolation::Bezier(0.0),
+            Interpolation::StrokeBezier(0.0, 0.0),
+        ];
+
+        let supported_names: Vec<_> = all_modes
+            .into_iter()
+            .filter(|mode| {
+                let supported = is_supported_interpolation_mode(mode);
+                let mapped: Result<Interpolation<f32, f32>, _> = mode.map_argument();
+                assert_eq!(supported, mapped.is_ok(), "{mode:?}");
+                supported
+            })
+            .map(|mode| serde_json::to_value(mode).unwrap())
+            .collect();
+
+        assert_eq!(supported_names, SUPPORTED_INTERPOLATION_MODES);
+    }
+
+    #[test]
+    fn uniform_samples_span_whole_spline() {
+        let spline =
+            TimedSpline::<f32>::try_new_transition_timed(1.0, 3.0, Duration::from_secs(2)).unwrap();
+
+        let samples = spline.sample_uniform(5);
+
+        assert_eq!(samples.len(), 5);
+        assert_eq!(samples[0], (Duration::ZERO, spline.start_position()));
+        assert_eq!(samples[4], (Duration::from_secs(2), spline.end_position()));
+        let time_points: Vec<_> = samples.iter().map(|(time_point, _)| *time_point).collect();
+        assert_eq!(
+            time_points,
+            [0, 500, 1000, 1500, 2000].map(Duration::from_millis)
+        );
+        assert!(samples.windows(2).all(|window| window[0].1 < window[1].1));
+
+        assert_eq!(
+            spline.sample_uniform(1),
+            [(Duration::ZERO, spline.start_position())]
+        );
+        assert!(spline.sample_uniform(0).is_empty());
+    }
 }
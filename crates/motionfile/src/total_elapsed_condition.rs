@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use crate::condition::{Condition, Response, TimeOut};
+
+use serde::{Deserialize, Serialize};
+use types::condition_input::ConditionInput;
+
+/// Waits until the given time passed since the motion started, independent of how long the
+/// current frame has been waiting. Time in which an interrupt condition held the motion does not
+/// count. Useful as a watchdog, e.g. to give up waiting for stability late in a stand-up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TotalElapsed(#[serde(with = "crate::serde_seconds")] pub Duration);
+
+impl Condition for TotalElapsed {
+    /// Without knowing when the motion started, the condition can only wait.
+    fn evaluate(&self, _condition_input: &ConditionInput) -> Response {
+        Response::Wait
+    }
+
+    fn evaluate_in_motion(
+        &self,
+        _condition_input: &ConditionInput,
+        _cycles_since_start: usize,
+        elapsed_since_motion_start: Duration,
+    ) -> Response {
+        if elapsed_since_motion_start >= self.0 {
+            return Response::Continue;
+        }
+        Response::Wait
+    }
+}
+
+impl TimeOut for TotalElapsed {
+    fn timeout(&self, _time_since_start: Duration) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DiscreteConditionType;
+
+    use super::*;
+
+    #[test]
+    fn continues_once_the_motion_ran_long_enough() {
+        let condition: DiscreteConditionType =
+            serde_json::from_str(r#"{"TotalElapsed": 1.5}"#).unwrap();
+        let condition_input = ConditionInput::default();
+
+        let responses: Vec<_> = [0, 1000, 1500, 2000]
+            .map(|milliseconds| {
+                condition.evaluate_in_motion(
+                    &condition_input,
+                    0,
+                    Duration::from_millis(milliseconds),
+                )
+            })
+            .into();
+
+        assert_eq!(
+            responses,
+            [
+                Response::Wait,
+                Response::Wait,
+                Response::Continue,
+                Response::Continue
+            ]
+        );
+        assert!(!condition.timeout(Duration::from_secs(1000)));
+    }
+}
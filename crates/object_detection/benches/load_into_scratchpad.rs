@@ -0,0 +1,68 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use object_detection::pose_detection::{
+    load_into_scratchpad, load_into_scratchpad_parallel, DetectionCrop, NetworkInputSize,
+};
+use rayon::ThreadPoolBuilder;
+use types::{color::YCbCr422, ycbcr422_image::YCbCr422Image};
+
+const IMAGE_WIDTH: u32 = 640;
+const IMAGE_HEIGHT: u32 = 480;
+
+fn representative_image() -> YCbCr422Image {
+    let buffer = (0..IMAGE_WIDTH / 2 * IMAGE_HEIGHT)
+        .map(|index| YCbCr422 {
+            y1: (index * 7 % 256) as u8,
+            cb: (index * 13 % 256) as u8,
+            y2: (index * 17 % 256) as u8,
+            cr: (index * 23 % 256) as u8,
+        })
+        .collect();
+    YCbCr422Image::from_ycbcr_buffer(IMAGE_WIDTH / 2, IMAGE_HEIGHT, buffer)
+}
+
+fn load_into_scratchpad_benchmark(criterion: &mut Criterion) {
+    let image = representative_image();
+    let input_size = NetworkInputSize::default();
+    let mut scratchpad = vec![0.0; input_size.scratchpad_size()];
+
+    criterion.bench_function("load_into_scratchpad standard crop", |bencher| {
+        bencher.iter(|| {
+            load_into_scratchpad(
+                black_box(&mut scratchpad),
+                black_box(&image),
+                DetectionCrop::standard(input_size, 224),
+            )
+        })
+    });
+    criterion.bench_function("load_into_scratchpad zoomed crop", |bencher| {
+        bencher.iter(|| {
+            load_into_scratchpad(
+                black_box(&mut scratchpad),
+                black_box(&image),
+                DetectionCrop::zoomed(input_size, 224),
+            )
+        })
+    });
+    for threads in [2, 4] {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("failed to create thread pool");
+        criterion.bench_function(
+            &format!("load_into_scratchpad standard crop on {threads} threads"),
+            |bencher| {
+                bencher.iter(|| {
+                    load_into_scratchpad_parallel(
+                        black_box(&mut scratchpad),
+                        black_box(&image),
+                        DetectionCrop::standard(input_size, 224),
+                        &thread_pool,
+                    )
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, load_into_scratchpad_benchmark);
+criterion_main!(benches);
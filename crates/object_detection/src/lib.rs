@@ -1,2 +1,3 @@
 pub mod pose_detection;
 pub mod pose_interpretation;
+pub mod pose_log;
@@ -1,56 +1,288 @@
-use std::{
-    path::PathBuf,
-    time::{Duration, SystemTime},
-};
+#[cfg(feature = "openvino")]
+use std::{fs::File, io::BufReader, path::Path, time::SystemTime};
+use std::{ops::Range, path::PathBuf, sync::OnceLock, time::Duration};
 
-use color_eyre::{
-    eyre::{Context, ContextCompat},
-    Result,
-};
+#[cfg(feature = "openvino")]
+use crate::pose_log::PoseLogger;
+use color_eyre::Result;
 use context_attribute::context;
 use coordinate_systems::Pixel;
-use framework::{deserialize_not_implemented, AdditionalOutput, MainOutput};
+#[cfg(feature = "openvino")]
+use framework::deserialize_not_implemented;
+use framework::{AdditionalOutput, MainOutput};
 use geometry::rectangle::Rectangle;
 use hardware::{PathsInterface, TimeInterface};
+#[cfg(any(feature = "openvino", test))]
 use itertools::Itertools;
-use linear_algebra::{point, vector};
-use ndarray::{s, ArrayView};
-use openvino::{Blob, Core, ExecutableNetwork, Layout, Precision, TensorDesc};
+use linear_algebra::point;
+#[cfg(any(feature = "openvino", test))]
+use linear_algebra::vector;
+use ndarray::ShapeError;
+#[cfg(any(feature = "openvino", test))]
+use ndarray::{s, ArrayView, ArrayView3};
+#[cfg(feature = "openvino")]
+use openvino::{
+    Blob, Core, ExecutableNetwork, InferRequest, InferenceError, Layout, Precision, SetupError,
+    TensorDesc,
+};
+#[cfg(any(feature = "openvino", test))]
+use rayon::ThreadPoolBuilder;
+use rayon::{
+    prelude::{IndexedParallelIterator, ParallelIterator, ParallelSliceMut},
+    ThreadPool, ThreadPoolBuildError,
+};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+#[cfg(feature = "openvino")]
+use types::pose_detection::Keypoint;
+#[cfg(any(feature = "openvino", test))]
+use types::{bounding_box::BoundingBox, pose_detection::Keypoints};
 use types::{
-    bounding_box::BoundingBox,
-    color::Rgb,
+    color::YCbCr444,
     motion_command::MotionCommand,
-    pose_detection::{HumanPose, Keypoints},
+    pose_detection::{
+        EdgePenalty, HumanPose, KeypointLayout, KeypointSpace, KeypointsError, LuminanceAdaptation,
+        ScoreCalibration,
+    },
     ycbcr422_image::YCbCr422Image,
 };
 
+/// Input size of the shipped model, used until the size of the loaded model is known.
 const DETECTION_IMAGE_HEIGHT: usize = 480;
 const DETECTION_IMAGE_WIDTH: usize = 192;
-const DETECTION_IMAGE_START_X: usize = (640 - DETECTION_IMAGE_WIDTH) / 2;
 const DETECTION_NUMBER_CHANNELS: usize = 3;
 
-const MAX_DETECTION: usize = 1890;
+/// Downsampling factors of the detection heads, each head predicts one detection per cell.
+#[cfg(any(feature = "openvino", test))]
+const DETECTION_HEAD_STRIDES: [usize; 3] = [8, 16, 32];
+/// Each detection starts with the bounding box center and size followed by its score.
+#[cfg(feature = "openvino")]
+const BOUNDING_BOX_VALUES: usize = 5;
+
+#[cfg(feature = "openvino")]
+const POSE_LOG_PATH: &str = "logs/human_poses.jsonl";
+
+/// Keypoints within this fraction of the bounding box size around the box still count as inside.
+#[cfg(feature = "openvino")]
+const KEYPOINT_BOUNDING_BOX_MARGIN: f32 = 0.1;
+
+/// The zoomed crop covers the upper part of the standard crop at twice the resolution, where
+/// distant people appear too small for the standard crop.
+const ZOOMED_CROP_SCALE: f32 = 0.5;
+
+/// Height and width of the network input in pixels, read from the loaded model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct NetworkInputSize {
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Default for NetworkInputSize {
+    fn default() -> Self {
+        Self {
+            height: DETECTION_IMAGE_HEIGHT,
+            width: DETECTION_IMAGE_WIDTH,
+        }
+    }
+}
+
+impl NetworkInputSize {
+    /// Number of values of a single network input.
+    pub fn scratchpad_size(self) -> usize {
+        self.stride() * DETECTION_NUMBER_CHANNELS
+    }
 
-const DETECTION_SCRATCHPAD_SIZE: usize =
-    DETECTION_IMAGE_WIDTH * DETECTION_IMAGE_HEIGHT * DETECTION_NUMBER_CHANNELS;
+    /// Number of values of a single channel of the network input.
+    fn stride(self) -> usize {
+        self.height * self.width
+    }
 
-const STRIDE: usize = DETECTION_IMAGE_HEIGHT * DETECTION_IMAGE_WIDTH;
+    #[cfg(any(feature = "openvino", test))]
+    fn number_of_detections(self) -> usize {
+        DETECTION_HEAD_STRIDES
+            .iter()
+            .map(|stride| (self.height / stride) * (self.width / stride))
+            .sum()
+    }
+}
+
+/// Region of the image fed into the network. A pixel `(x, y)` of the network input corresponds to
+/// the image pixel `start + scale * (x, y)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetectionCrop {
+    input_size: NetworkInputSize,
+    start_x: f32,
+    start_y: f32,
+    scale: f32,
+}
+
+impl DetectionCrop {
+    pub fn standard(input_size: NetworkInputSize, crop_start_x: usize) -> Self {
+        Self {
+            input_size,
+            start_x: crop_start_x as f32,
+            start_y: 0.0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn zoomed(input_size: NetworkInputSize, crop_start_x: usize) -> Self {
+        let width = input_size.width as f32;
+        Self {
+            input_size,
+            start_x: crop_start_x as f32 + (width - width * ZOOMED_CROP_SCALE) / 2.0,
+            start_y: 0.0,
+            scale: ZOOMED_CROP_SCALE,
+        }
+    }
+
+    /// Crop around `area` grown by `margin` times its size on every side and kept inside the
+    /// image. The resolution lies between the ones of the standard and the zoomed crop.
+    pub fn around(
+        input_size: NetworkInputSize,
+        area: Rectangle<Pixel>,
+        margin: f32,
+        image_width: u32,
+        image_height: u32,
+    ) -> Self {
+        let size = area.max - area.min;
+        let scale = (size.x() * (1.0 + 2.0 * margin) / input_size.width as f32)
+            .max(size.y() * (1.0 + 2.0 * margin) / input_size.height as f32)
+            .clamp(ZOOMED_CROP_SCALE, 1.0);
+        let center = area.min + size / 2.0;
+        let width = input_size.width as f32 * scale;
+        let height = input_size.height as f32 * scale;
+        Self {
+            input_size,
+            start_x: (center.x() - width / 2.0).clamp(0.0, (image_width as f32 - width).max(0.0)),
+            start_y: (center.y() - height / 2.0)
+                .clamp(0.0, (image_height as f32 - height).max(0.0)),
+            scale,
+        }
+    }
+
+    /// The image region covered by the network input.
+    pub fn area(self) -> Rectangle<Pixel> {
+        let (min_x, min_y) = self.to_image(0.0, 0.0);
+        let (max_x, max_y) =
+            self.to_image(self.input_size.width as f32, self.input_size.height as f32);
+        Rectangle {
+            min: point![min_x, min_y],
+            max: point![max_x, max_y],
+        }
+    }
+
+    fn to_image(self, x: f32, y: f32) -> (f32, f32) {
+        (self.start_x + x * self.scale, self.start_y + y * self.scale)
+    }
+
+    #[cfg(feature = "openvino")]
+    fn keypoints_to_image(self, keypoints: Keypoints) -> Keypoints {
+        let keypoints: [Keypoint; 17] = keypoints.into();
+        keypoints
+            .map(|keypoint| {
+                let (x, y) = self.to_image(keypoint.point.x(), keypoint.point.y());
+                Keypoint {
+                    point: point![x, y],
+                    confidence: keypoint.confidence,
+                }
+            })
+            .into()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum PoseDetectionError {
+    #[error("detection model {path:?} not found")]
+    ModelNotFound { path: PathBuf },
+    #[error("network output does not have the expected shape")]
+    InvalidOutputShape(#[from] ShapeError),
+    #[error("network output does not match the keypoint layout")]
+    InvalidKeypoints(#[from] KeypointsError),
+    #[error("failed to read keypoint layout {path:?}")]
+    KeypointLayoutUnreadable {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse keypoint layout {path:?}")]
+    KeypointLayoutInvalid {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error(
+        "network output has {actual} values per detection, keypoint layout expects at least {expected}"
+    )]
+    OutputChannelMismatch { expected: usize, actual: usize },
+    #[error("network input has dimensions {actual:?}, expected NCHW with {channels} channels")]
+    InputShapeMismatch { channels: usize, actual: Vec<usize> },
+    #[error("failed to create preprocessing thread pool")]
+    ThreadPoolFailed(#[from] ThreadPoolBuildError),
+    #[cfg(feature = "openvino")]
+    #[error("failed to set up inference engine")]
+    SetupFailed(#[from] SetupError),
+    #[cfg(feature = "openvino")]
+    #[error("failed to {action}")]
+    InferenceFailed {
+        action: &'static str,
+        source: InferenceError,
+    },
+    #[cfg(feature = "openvino")]
+    #[error("network input has layout {actual:?}, expected {expected:?}")]
+    InputLayoutMismatch { expected: Layout, actual: Layout },
+    #[cfg(feature = "openvino")]
+    #[error("network {tensor} has precision {actual:?}, expected {expected:?}")]
+    PrecisionMismatch {
+        tensor: &'static str,
+        expected: Precision,
+        actual: Precision,
+    },
+}
+
+#[cfg(feature = "openvino")]
+fn inference_failed(action: &'static str) -> impl FnOnce(InferenceError) -> PoseDetectionError {
+    move |source| PoseDetectionError::InferenceFailed { action, source }
+}
 
 #[derive(Deserialize, Serialize)]
 pub struct PoseDetection {
+    #[cfg(feature = "openvino")]
     #[serde(skip, default = "deserialize_not_implemented")]
     scratchpad: Vec<f32>,
+    /// Has to outlive `infer_request`, which was created from it.
+    #[cfg(feature = "openvino")]
     #[serde(skip, default = "deserialize_not_implemented")]
-    network: ExecutableNetwork,
+    _network: ExecutableNetwork,
+    /// Reused across cycles, every inference overwrites the input blob and the outputs.
+    #[cfg(feature = "openvino")]
+    #[serde(skip, default = "deserialize_not_implemented")]
+    infer_request: InferRequest,
+
+    keypoint_layout: KeypointLayout,
+    input_size: NetworkInputSize,
 
+    #[cfg(feature = "openvino")]
     input_name: String,
+    #[cfg(feature = "openvino")]
     output_name: String,
+    #[cfg(feature = "openvino")]
+    inference_schedule: InferenceSchedule,
+    #[cfg(feature = "openvino")]
+    region_of_interest: RegionOfInterest,
+
+    #[cfg(feature = "openvino")]
+    #[serde(skip)]
+    pose_logger: Option<PoseLogger>,
+    /// Only present while more than one preprocessing thread is configured.
+    #[cfg(feature = "openvino")]
+    #[serde(skip)]
+    preprocess_thread_pool: Option<ThreadPool>,
 }
 
 #[context]
 pub struct CreationContext {
     hardware_interface: HardwareInterface,
+
+    warm_up_inferences: Parameter<usize, "object_detection.$cycler_instance.warm_up_inferences">,
 }
 
 #[context]
@@ -60,6 +292,16 @@ pub struct CycleContext {
     preprocess_duration: AdditionalOutput<Duration, "preprocess_duration">,
     inference_duration: AdditionalOutput<Duration, "inference_duration">,
     postprocess_duration: AdditionalOutput<Duration, "postprocess_duration">,
+    /// Image region of the standard crop or of the crop around the last detection. The zoomed
+    /// crop lies within the upper part of the standard crop.
+    detection_crop: AdditionalOutput<Rectangle<Pixel>, "detection_crop">,
+    /// Whether the network ran in this cycle, which tells skipped detection apart from a cycle
+    /// without detected poses.
+    detection_ran: AdditionalOutput<bool, "detection_ran">,
+    /// Poses scoring between `rejected_pose_threshold` and the acceptance threshold, in image
+    /// pixel coordinates and without non-maximum suppression across crops. Helps to tell whether
+    /// the acceptance threshold is too strict.
+    rejected_poses: AdditionalOutput<Vec<HumanPose>, "rejected_poses">,
 
     image: Input<YCbCr422Image, "image">,
     motion_command: Input<MotionCommand, "Control", "motion_command">,
@@ -68,54 +310,133 @@ pub struct CycleContext {
         Parameter<f32, "object_detection.$cycler_instance.intersection_over_union_threshold">,
     keypoint_confidence_threshold:
         Parameter<f32, "object_detection.$cycler_instance.keypoint_confidence_threshold">,
+    /// Lowest score of poses reported as `rejected_poses`, only used while subscribed.
+    rejected_pose_threshold:
+        Parameter<f32, "object_detection.$cycler_instance.rejected_pose_threshold">,
+    /// Calibrated detection score above which a person counts as present.
+    presence_threshold: Parameter<f32, "object_detection.$cycler_instance.presence_threshold">,
+    score_calibration:
+        Parameter<ScoreCalibration, "object_detection.$cycler_instance.score_calibration">,
+    luminance_adaptation:
+        Parameter<LuminanceAdaptation, "object_detection.$cycler_instance.luminance_adaptation">,
+    /// Applied to detections close to the border of the network input, disabled if `None`.
+    edge_penalty: Parameter<Option<EdgePenalty>, "object_detection.$cycler_instance.edge_penalty?">,
+    merge_suppressed_keypoints:
+        Parameter<bool, "object_detection.$cycler_instance.merge_suppressed_keypoints">,
+    keypoint_space: Parameter<KeypointSpace, "object_detection.$cycler_instance.keypoint_space">,
+    use_generalized_intersection_over_union: Parameter<
+        bool,
+        "object_detection.$cycler_instance.use_generalized_intersection_over_union",
+    >,
+    /// Non-maximum suppression ranks poses by this weight times the bounding box score plus the
+    /// remaining weight times the mean keypoint confidence.
+    box_score_weight: Parameter<f32, "object_detection.$cycler_instance.box_score_weight">,
+    /// Poses whose combined score is below this floor are dropped before non-maximum
+    /// suppression instead of being compared to every better pose, disabled if `None`.
+    non_maximum_suppression_score_floor: Parameter<
+        Option<f32>,
+        "object_detection.$cycler_instance.non_maximum_suppression_score_floor?",
+    >,
+    allowed_bounding_box_aspect_ratio: Parameter<
+        Range<f32>,
+        "object_detection.$cycler_instance.allowed_bounding_box_aspect_ratio",
+    >,
     enable: Parameter<bool, "object_detection.$cycler_instance.enable">,
+    dry_run: Parameter<bool, "object_detection.$cycler_instance.dry_run">,
+    /// Inference runs every `inference_period` cycles, the cycles in between return the poses of
+    /// the last inference.
+    inference_period: Parameter<usize, "object_detection.$cycler_instance.inference_period">,
+    /// Number of skipped cycles after which the poses of the last inference are dropped.
+    maximum_cached_poses_age:
+        Parameter<usize, "object_detection.$cycler_instance.maximum_cached_poses_age">,
+    /// When disabled, the network input of the previous cycle is reused.
+    run_preprocess: Parameter<bool, "object_detection.$cycler_instance.run_preprocess">,
+    /// Number of threads converting the image rows into the network input, at most one
+    /// preprocesses serially on the vision thread.
+    preprocess_threads: Parameter<usize, "object_detection.$cycler_instance.preprocess_threads">,
+    /// When disabled, the network output is replaced by zeros, so no poses are detected.
+    run_inference: Parameter<bool, "object_detection.$cycler_instance.run_inference">,
+    /// When disabled, the network output is not decoded and no poses are returned.
+    run_postprocess: Parameter<bool, "object_detection.$cycler_instance.run_postprocess">,
+    crop_offset_x: Parameter<i32, "object_detection.$cycler_instance.crop_offset_x">,
+    log_poses: Parameter<bool, "object_detection.$cycler_instance.log_poses">,
+    enable_multi_scale: Parameter<bool, "object_detection.$cycler_instance.enable_multi_scale">,
+    /// When enabled, the network input is cropped around the last detection instead of the
+    /// center of the image until no detection was made for `region_of_interest_timeout`.
+    enable_region_of_interest:
+        Parameter<bool, "object_detection.$cycler_instance.enable_region_of_interest">,
+    /// Fraction of the last bounding box size added on every side of the region of interest.
+    region_of_interest_margin:
+        Parameter<f32, "object_detection.$cycler_instance.region_of_interest_margin">,
+    region_of_interest_timeout:
+        Parameter<Duration, "object_detection.$cycler_instance.region_of_interest_timeout">,
+    maximum_fraction_of_keypoints_outside_bounding_box: Parameter<
+        Option<f32>,
+        "object_detection.$cycler_instance.maximum_fraction_of_keypoints_outside_bounding_box?",
+    >,
 }
 
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
     pub human_poses: MainOutput<Vec<HumanPose>>,
+    /// Whether any detection reaches the presence threshold, even without a detected pose. In
+    /// cycles without inference, a person counts as present if cached poses are returned.
+    pub person_present: MainOutput<bool>,
 }
 
 impl PoseDetection {
-    pub fn new(context: CreationContext<impl PathsInterface>) -> Result<Self> {
-        let paths = context.hardware_interface.get_paths();
-        let neural_network_folder = paths.neural_networks;
-
-        let model_xml_name = PathBuf::from("yolov8n-pose-ov.xml");
-
-        let model_path = neural_network_folder.join(&model_xml_name);
-        let weights_path = neural_network_folder.join(model_xml_name.with_extension("bin"));
-
-        let mut core = Core::new(None)?;
-        let mut network = core
-            .read_network_from_file(
-                model_path
-                    .to_str()
-                    .wrap_err("failed to get detection model path")?,
-                weights_path
-                    .to_str()
-                    .wrap_err("failed to get detection weights path")?,
-            )
-            .wrap_err("failed to create detection network")?;
+    #[cfg(not(feature = "openvino"))]
+    pub fn new(_context: CreationContext<impl PathsInterface>) -> Result<Self> {
+        Ok(Self {
+            keypoint_layout: KeypointLayout::coco(),
+            input_size: NetworkInputSize::default(),
+        })
+    }
 
-        let input_name = network.get_input_name(0)?;
-        let output_name = network.get_output_name(0)?;
+    #[cfg(not(feature = "openvino"))]
+    pub fn cycle(&mut self, mut context: CycleContext<impl TimeInterface>) -> Result<MainOutputs> {
+        context.detection_ran.fill_if_subscribed(|| false);
+        Ok(MainOutputs::default())
+    }
 
-        network
-            .set_input_layout(&input_name, Layout::NCHW)
-            .wrap_err("failed to set input data format")?;
+    #[cfg(feature = "openvino")]
+    pub fn new(context: CreationContext<impl PathsInterface>) -> Result<Self> {
+        let paths = context.hardware_interface.get_paths();
+        let (mut network, input_name, output_name) = load_network(&paths.neural_networks)?;
+        let keypoint_layout = load_keypoint_layout(&paths.neural_networks)?;
+        let mut infer_request = network
+            .create_infer_request()
+            .map_err(inference_failed("create infer request"))?;
+        let input_size = discover_input_size(&mut infer_request, &input_name)?;
+        let scratchpad = vec![0.0; input_size.scratchpad_size()];
+        warm_up(
+            &mut infer_request,
+            &input_name,
+            &scratchpad,
+            input_size,
+            *context.warm_up_inferences,
+        )?;
 
         Ok(Self {
-            scratchpad: vec![0.0; DETECTION_SCRATCHPAD_SIZE],
-            network: core.load_network(&network, "CPU")?,
+            scratchpad,
+            _network: network,
+            infer_request,
+            keypoint_layout,
+            input_size,
             input_name,
             output_name,
+            inference_schedule: InferenceSchedule::default(),
+            region_of_interest: RegionOfInterest::default(),
+            pose_logger: None,
+            preprocess_thread_pool: None,
         })
     }
 
+    #[cfg(feature = "openvino")]
     pub fn cycle(&mut self, mut context: CycleContext<impl TimeInterface>) -> Result<MainOutputs> {
         if !context.enable {
+            context.detection_ran.fill_if_subscribed(|| false);
             return Ok(MainOutputs::default());
         }
 
@@ -127,14 +448,166 @@ impl PoseDetection {
             }
         );
         if !should_look_for_referee {
+            self.inference_schedule = InferenceSchedule::default();
+            self.region_of_interest = RegionOfInterest::default();
+            context.detection_ran.fill_if_subscribed(|| false);
             return Ok(MainOutputs::default());
         };
+        if !self
+            .inference_schedule
+            .should_run(*context.inference_period)
+        {
+            context.detection_ran.fill_if_subscribed(|| false);
+            let cached_poses = self
+                .inference_schedule
+                .skip(*context.maximum_cached_poses_age);
+            return Ok(MainOutputs {
+                person_present: (!cached_poses.is_empty()).into(),
+                human_poses: cached_poses.into(),
+            });
+        }
 
         let image = context.image;
-        {
+        let now = context.hardware_interface.get_now();
+        let input_size = self.input_size;
+        let crop_start_x = crop_start_x(*context.crop_offset_x, image.width(), input_size.width);
+        let region_of_interest_crop = if *context.enable_region_of_interest {
+            self.region_of_interest
+                .crop(now, *context.region_of_interest_timeout)
+                .map(|area| {
+                    DetectionCrop::around(
+                        input_size,
+                        area,
+                        *context.region_of_interest_margin,
+                        image.width(),
+                        image.height(),
+                    )
+                })
+        } else {
+            None
+        };
+        let inputs = match region_of_interest_crop {
+            Some(crop) => vec![(image, crop)],
+            None if *context.enable_multi_scale => vec![
+                (image, DetectionCrop::standard(input_size, crop_start_x)),
+                (image, DetectionCrop::zoomed(input_size, crop_start_x)),
+            ],
+            None => vec![(image, DetectionCrop::standard(input_size, crop_start_x))],
+        };
+        context
+            .detection_crop
+            .fill_if_subscribed(|| inputs[0].1.area());
+        let (poses, person_present) = self.detect_poses(&inputs, &mut context)?;
+        let poses = poses.into_iter().flatten().collect_vec();
+        context
+            .detection_ran
+            .fill_if_subscribed(|| !*context.dry_run && *context.run_inference);
+        let poses = if inputs.len() > 1 {
+            non_maximum_suppression(
+                poses,
+                *context.intersection_over_union_threshold,
+                *context.merge_suppressed_keypoints,
+                *context.use_generalized_intersection_over_union,
+                *context.box_score_weight,
+                context.non_maximum_suppression_score_floor.copied(),
+            )
+        } else {
+            poses
+        };
+        let poses = match context.maximum_fraction_of_keypoints_outside_bounding_box {
+            Some(maximum_fraction) => reject_inconsistent_poses(
+                poses,
+                *context.keypoint_confidence_threshold,
+                *maximum_fraction,
+            ),
+            None => poses,
+        };
+
+        self.region_of_interest.update(&poses, now);
+
+        if *context.log_poses {
+            let pose_logger = match &mut self.pose_logger {
+                Some(pose_logger) => pose_logger,
+                None => self.pose_logger.insert(PoseLogger::open(POSE_LOG_PATH)?),
+            };
+            pose_logger.log(now, &poses)?;
+        } else {
+            self.pose_logger = None;
+        }
+
+        let poses = match context.keypoint_space {
+            KeypointSpace::Pixel => poses,
+            KeypointSpace::Normalized => poses
+                .into_iter()
+                .map(|pose| HumanPose {
+                    keypoints: pose
+                        .keypoints
+                        .to_normalized(image.width() as f32, image.height() as f32),
+                    ..pose
+                })
+                .collect(),
+        };
+        self.inference_schedule.store(&poses);
+
+        Ok(MainOutputs {
+            human_poses: poses.into(),
+            person_present: person_present.into(),
+        })
+    }
+
+    /// Keeps a thread pool with `threads` threads for preprocessing, or none if at most one
+    /// thread is configured. The pool is only rebuilt if the number of threads changes.
+    #[cfg(feature = "openvino")]
+    fn update_preprocess_thread_pool(&mut self, threads: usize) -> Result<(), PoseDetectionError> {
+        let configured_threads = (threads > 1).then_some(threads);
+        let current_threads = self
+            .preprocess_thread_pool
+            .as_ref()
+            .map(|thread_pool| thread_pool.current_num_threads());
+        if current_threads != configured_threads {
+            self.preprocess_thread_pool = configured_threads
+                .map(|threads| {
+                    ThreadPoolBuilder::new()
+                        .num_threads(threads)
+                        .thread_name(|index| format!("pose_preprocess_{index}"))
+                        .build()
+                })
+                .transpose()?;
+        }
+        Ok(())
+    }
+
+    /// Runs each image crop through the network and returns the detected poses per crop in image
+    /// coordinates together with whether a person is present. The loaded network has a fixed
+    /// batch size of one, so every crop is inferred separately.
+    #[cfg(feature = "openvino")]
+    fn detect_poses(
+        &mut self,
+        inputs: &[(&YCbCr422Image, DetectionCrop)],
+        context: &mut CycleContext<impl TimeInterface>,
+    ) -> Result<(Vec<Vec<HumanPose>>, bool), PoseDetectionError> {
+        let batch_size = inputs.len();
+        let scratchpad_size = self.input_size.scratchpad_size();
+        let number_of_detections = self.input_size.number_of_detections();
+        self.scratchpad.resize(batch_size * scratchpad_size, 0.0);
+        let mut mean_luminances = vec![None; batch_size];
+        if *context.run_preprocess {
             let earlier = context.hardware_interface.get_now();
 
-            load_into_scratchpad(&mut self.scratchpad, image);
+            self.update_preprocess_thread_pool(*context.preprocess_threads)?;
+            for ((scratchpad, (image, crop)), mean_luminance) in self
+                .scratchpad
+                .chunks_exact_mut(scratchpad_size)
+                .zip(inputs)
+                .zip(&mut mean_luminances)
+            {
+                *mean_luminance = Some(match &self.preprocess_thread_pool {
+                    Some(thread_pool) => {
+                        load_into_scratchpad_parallel(scratchpad, image, *crop, thread_pool)
+                    }
+                    None => load_into_scratchpad(scratchpad, image, *crop),
+                });
+            }
 
             context.preprocess_duration.fill_if_subscribed(|| {
                 context
@@ -145,24 +618,55 @@ impl PoseDetection {
             });
         }
 
-        let mut infer_request = self.network.create_infer_request()?;
-
-        let tensor_description = TensorDesc::new(
-            Layout::NCHW,
-            &[
-                1,
-                DETECTION_NUMBER_CHANNELS,
-                DETECTION_IMAGE_HEIGHT,
-                DETECTION_IMAGE_WIDTH,
-            ],
-            Precision::FP32,
-        );
-        let blob = Blob::new(&tensor_description, self.scratchpad[..].as_bytes())?;
-        {
+        let values_per_detection = BOUNDING_BOX_VALUES + self.keypoint_layout.number_of_values();
+        let (prediction, output_channels) = if *context.dry_run || !*context.run_inference {
+            (
+                vec![0.0; batch_size * values_per_detection * number_of_detections],
+                values_per_detection,
+            )
+        } else {
+            let tensor_description = TensorDesc::new(
+                Layout::NCHW,
+                &[
+                    1,
+                    DETECTION_NUMBER_CHANNELS,
+                    self.input_size.height,
+                    self.input_size.width,
+                ],
+                Precision::FP32,
+            );
             let earlier = SystemTime::now();
-
-            infer_request.set_blob(&self.input_name, &blob)?;
-            infer_request.infer()?;
+            let mut prediction = Vec::new();
+            let mut output_channels = 0;
+            for scratchpad in self.scratchpad.chunks_exact(scratchpad_size) {
+                let blob = Blob::new(&tensor_description, scratchpad.as_bytes())
+                    .map_err(inference_failed("create input blob"))?;
+                self.infer_request
+                    .set_blob(&self.input_name, &blob)
+                    .map_err(inference_failed("set input blob"))?;
+                self.infer_request
+                    .infer()
+                    .map_err(inference_failed("run inference"))?;
+                let mut prediction_blob = self
+                    .infer_request
+                    .get_blob("output0")
+                    .map_err(inference_failed("get output blob"))?;
+                let output_description = prediction_blob
+                    .tensor_desc()
+                    .map_err(inference_failed("get output description"))?;
+                let precision = output_description.precision();
+                if precision != Precision::FP32 {
+                    return Err(PoseDetectionError::PrecisionMismatch {
+                        tensor: "output",
+                        expected: Precision::FP32,
+                        actual: precision,
+                    });
+                }
+                output_channels = output_description.dims().get(1).copied().unwrap_or(0);
+                prediction.extend_from_slice(unsafe {
+                    prediction_blob.buffer_mut_as_type::<f32>().unwrap()
+                });
+            }
             context.inference_duration.fill_if_subscribed(|| {
                 context
                     .hardware_interface
@@ -170,47 +674,124 @@ impl PoseDetection {
                     .duration_since(earlier)
                     .expect("time ran backwards")
             });
+            (prediction, output_channels)
+        };
+        let prediction = pose_channels(
+            &prediction,
+            batch_size,
+            output_channels,
+            values_per_detection,
+            number_of_detections,
+        )?;
+        if !*context.run_postprocess {
+            return Ok((vec![Vec::new(); batch_size], false));
         }
-        let mut prediction = infer_request.get_blob("output0")?;
-        let prediction = unsafe { prediction.buffer_mut_as_type::<f32>().unwrap() };
-        let prediction = ArrayView::from_shape((56, MAX_DETECTION), prediction)?;
 
         let earlier = SystemTime::now();
+        let person_present = person_present(
+            prediction
+                .slice(s![.., 4, ..])
+                .iter()
+                .map(|&score| context.score_calibration.calibrate(score)),
+            *context.presence_threshold,
+        );
+        let rejection_threshold = context
+            .rejected_poses
+            .is_subscribed()
+            .then_some(*context.rejected_pose_threshold);
+        let mut rejected_poses = Vec::new();
         let poses = prediction
-            .columns()
-            .into_iter()
-            .filter_map(|row| {
-                let probability = row[4];
-                if probability < *context.keypoint_confidence_threshold {
-                    return None;
-                }
-                let bounding_box_slice = row.slice(s![0..4]);
+            .outer_iter()
+            .zip(inputs)
+            .zip(mean_luminances)
+            .map(|((prediction, (image, crop)), mean_luminance)| {
+                let score_threshold = *context.keypoint_confidence_threshold
+                    * mean_luminance.map_or(1.0, |mean_luminance| {
+                        context
+                            .luminance_adaptation
+                            .threshold_factor(mean_luminance)
+                    });
+                let decoding_threshold = rejection_threshold
+                    .map_or(score_threshold, |rejection_threshold| {
+                        rejection_threshold.min(score_threshold)
+                    });
+                let poses = prediction
+                    .columns()
+                    .into_iter()
+                    .filter_map(|row| {
+                        let probability = context.score_calibration.calibrate(row[4]);
+                        if probability < decoding_threshold {
+                            return None;
+                        }
+                        let bounding_box_slice = row.slice(s![0..4]);
 
-                // bbox re-scale
-                let center_x = bounding_box_slice[0] + DETECTION_IMAGE_START_X as f32;
-                let center_y = bounding_box_slice[1];
-                let center = point![center_x, center_y];
+                        // bbox re-scale
+                        let (center_x, center_y) =
+                            crop.to_image(bounding_box_slice[0], bounding_box_slice[1]);
+                        let center = point![center_x, center_y];
 
-                let width = bounding_box_slice[2];
-                let height = bounding_box_slice[3];
-                let size = vector![width, height];
+                        let width = bounding_box_slice[2] * crop.scale;
+                        let height = bounding_box_slice[3] * crop.scale;
+                        if !context
+                            .allowed_bounding_box_aspect_ratio
+                            .contains(&(height / width))
+                        {
+                            return None;
+                        }
+                        let size = vector![width, height];
+                        let area = Rectangle::<Pixel>::new_with_center_and_size(center, size);
+                        let probability = match context.edge_penalty {
+                            Some(edge_penalty) => {
+                                edge_penalty.penalized_score(area, crop.area(), probability)?
+                            }
+                            None => probability,
+                        };
+                        if probability < decoding_threshold {
+                            return None;
+                        }
 
-                let bounding_box = BoundingBox {
-                    area: Rectangle::<Pixel>::new_with_center_and_size(center, size),
-                    score: probability,
+                        let bounding_box = BoundingBox {
+                            area,
+                            score: probability,
+                        };
+
+                        let keypoints_slice = row.slice(s![BOUNDING_BOX_VALUES..]).to_vec();
+                        Some(
+                            Keypoints::try_new_with_layout(
+                                &keypoints_slice,
+                                &self.keypoint_layout,
+                                0.0,
+                                0.0,
+                            )
+                            .map(|keypoints| {
+                                HumanPose::new(bounding_box, crop.keypoints_to_image(keypoints))
+                            }),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let to_image = |pose: HumanPose| HumanPose {
+                    bounding_box: pose
+                        .bounding_box_in_image(image.width() as f32, image.height() as f32),
+                    ..pose
                 };
+                let (poses, rejected) =
+                    split_rejected_poses(poses, score_threshold, rejection_threshold);
+                rejected_poses.extend(rejected.into_iter().map(to_image));
 
-                let keypoints_slice = row.slice(s![5..]);
-                let keypoints = Keypoints::try_new(
-                    keypoints_slice.as_standard_layout().as_slice()?,
-                    DETECTION_IMAGE_START_X as f32,
-                    0.0,
-                )?;
-                Some(HumanPose::new(bounding_box, keypoints))
+                Ok(non_maximum_suppression(
+                    poses,
+                    *context.intersection_over_union_threshold,
+                    *context.merge_suppressed_keypoints,
+                    *context.use_generalized_intersection_over_union,
+                    *context.box_score_weight,
+                    context.non_maximum_suppression_score_floor.copied(),
+                )
+                .into_iter()
+                .map(to_image)
+                .collect())
             })
-            .collect_vec();
-
-        let poses = non_maximum_suppression(poses, *context.intersection_over_union_threshold);
+            .collect::<Result<_, PoseDetectionError>>()?;
+        context.rejected_poses.fill_if_subscribed(|| rejected_poses);
 
         context.postprocess_duration.fill_if_subscribed(|| {
             SystemTime::now()
@@ -218,62 +799,527 @@ impl PoseDetection {
                 .expect("time ran backwards")
         });
 
-        Ok(MainOutputs {
-            human_poses: poses.into(),
+        Ok((poses, person_present))
+    }
+}
+
+#[cfg(feature = "openvino")]
+fn load_network(
+    neural_network_folder: &Path,
+) -> Result<(ExecutableNetwork, String, String), PoseDetectionError> {
+    let model_xml_name = PathBuf::from("yolov8n-pose-ov.xml");
+
+    let model_path = neural_network_folder.join(&model_xml_name);
+    let weights_path = neural_network_folder.join(model_xml_name.with_extension("bin"));
+    for path in [&model_path, &weights_path] {
+        if !path.exists() {
+            return Err(PoseDetectionError::ModelNotFound { path: path.clone() });
+        }
+    }
+    let mut core = Core::new(None)?;
+    let mut network = core
+        .read_network_from_file(path_as_str(&model_path)?, path_as_str(&weights_path)?)
+        .map_err(inference_failed("create detection network"))?;
+
+    let input_name = network
+        .get_input_name(0)
+        .map_err(inference_failed("get input name"))?;
+    let output_name = network
+        .get_output_name(0)
+        .map_err(inference_failed("get output name"))?;
+
+    network
+        .set_input_layout(&input_name, Layout::NCHW)
+        .map_err(inference_failed("set input data format"))?;
+
+    let network = core
+        .load_network(&network, "CPU")
+        .map_err(inference_failed("load detection network"))?;
+    Ok((network, input_name, output_name))
+}
+
+/// Reads the keypoint layout stored next to the model, falling back to the COCO layout if the
+/// model comes without one.
+#[cfg(feature = "openvino")]
+fn load_keypoint_layout(
+    neural_network_folder: &Path,
+) -> Result<KeypointLayout, PoseDetectionError> {
+    let path = neural_network_folder.join("yolov8n-pose-ov.keypoints.json");
+    if !path.exists() {
+        return Ok(KeypointLayout::coco());
+    }
+    let file =
+        File::open(&path).map_err(|source| PoseDetectionError::KeypointLayoutUnreadable {
+            path: path.clone(),
+            source,
+        })?;
+    let layout: KeypointLayout = serde_json::from_reader(BufReader::new(file))
+        .map_err(|source| PoseDetectionError::KeypointLayoutInvalid { path, source })?;
+    layout.validate()?;
+    Ok(layout)
+}
+
+/// Reads the input size of the loaded network and checks that it takes the FP32 NCHW input
+/// written by `cycle`, so that a mismatching model fails at startup instead of with obscure
+/// errors during inference.
+#[cfg(feature = "openvino")]
+fn discover_input_size(
+    infer_request: &mut InferRequest,
+    input_name: &str,
+) -> Result<NetworkInputSize, PoseDetectionError> {
+    let input_description = infer_request
+        .get_blob(input_name)
+        .and_then(|blob| blob.tensor_desc())
+        .map_err(inference_failed("get input description"))?;
+    let layout = input_description.layout();
+    if layout != Layout::NCHW {
+        return Err(PoseDetectionError::InputLayoutMismatch {
+            expected: Layout::NCHW,
+            actual: layout,
+        });
+    }
+    let precision = input_description.precision();
+    if precision != Precision::FP32 {
+        return Err(PoseDetectionError::PrecisionMismatch {
+            tensor: "input",
+            expected: Precision::FP32,
+            actual: precision,
+        });
+    }
+    input_size_from_dimensions(input_description.dims())
+}
+
+/// The network is loaded with a batch size of one, the input has to be an RGB image of any size.
+#[cfg(any(feature = "openvino", test))]
+fn input_size_from_dimensions(
+    dimensions: &[usize],
+) -> Result<NetworkInputSize, PoseDetectionError> {
+    match *dimensions {
+        [_, DETECTION_NUMBER_CHANNELS, height, width] if height > 0 && width > 0 => {
+            Ok(NetworkInputSize { height, width })
+        }
+        _ => Err(PoseDetectionError::InputShapeMismatch {
+            channels: DETECTION_NUMBER_CHANNELS,
+            actual: dimensions.to_vec(),
+        }),
+    }
+}
+
+/// The first inferences after loading are much slower than steady state because kernels are
+/// compiled lazily. Running them on an empty image here keeps that spike out of the first cycle.
+#[cfg(feature = "openvino")]
+fn warm_up(
+    infer_request: &mut InferRequest,
+    input_name: &str,
+    scratchpad: &[f32],
+    input_size: NetworkInputSize,
+    number_of_inferences: usize,
+) -> Result<(), PoseDetectionError> {
+    if number_of_inferences == 0 {
+        return Ok(());
+    }
+    let tensor_description = TensorDesc::new(
+        Layout::NCHW,
+        &[
+            1,
+            DETECTION_NUMBER_CHANNELS,
+            input_size.height,
+            input_size.width,
+        ],
+        Precision::FP32,
+    );
+    let blob = Blob::new(&tensor_description, scratchpad.as_bytes())
+        .map_err(inference_failed("create warm-up input blob"))?;
+    infer_request
+        .set_blob(input_name, &blob)
+        .map_err(inference_failed("set warm-up input blob"))?;
+    for _ in 0..number_of_inferences {
+        infer_request
+            .infer()
+            .map_err(inference_failed("run warm-up inference"))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "openvino")]
+fn path_as_str(path: &Path) -> Result<&str, PoseDetectionError> {
+    path.to_str()
+        .ok_or_else(|| PoseDetectionError::ModelNotFound {
+            path: path.to_path_buf(),
         })
+}
+
+/// Shifts the centered crop of `crop_width` by `offset` pixels while keeping it inside the image.
+#[cfg(any(feature = "openvino", test))]
+fn crop_start_x(offset: i32, image_width: u32, crop_width: usize) -> usize {
+    let maximum_start_x = image_width.saturating_sub(crop_width as u32) as i64;
+    (maximum_start_x / 2 + offset as i64).clamp(0, maximum_start_x) as usize
+}
+
+/// Chroma terms of the YCbCr to RGB conversion of [`types::color::Rgb`], precomputed for every
+/// chroma value. Produces the same values as the conversion while saving its multiplications.
+struct ChromaLookupTable {
+    red_from_cr: [f32; 256],
+    green_from_cb: [f32; 256],
+    green_from_cr: [f32; 256],
+    blue_from_cb: [f32; 256],
+}
+
+impl ChromaLookupTable {
+    fn new() -> Self {
+        let centered = |value: usize| value as f32 - 128.0;
+        Self {
+            red_from_cr: std::array::from_fn(|cr| 1.40200 * centered(cr)),
+            green_from_cb: std::array::from_fn(|cb| 0.34414 * centered(cb)),
+            green_from_cr: std::array::from_fn(|cr| 0.71414 * centered(cr)),
+            blue_from_cb: std::array::from_fn(|cb| 1.77200 * centered(cb)),
+        }
+    }
+
+    /// The table is the same for every image, so it is only built on first use.
+    fn shared() -> &'static Self {
+        static LOOKUP_TABLE: OnceLock<ChromaLookupTable> = OnceLock::new();
+        LOOKUP_TABLE.get_or_init(Self::new)
+    }
+
+    fn normalized_rgb(&self, pixel: YCbCr444) -> [f32; 3] {
+        let y = pixel.y as f32;
+        let cb = pixel.cb as usize;
+        let cr = pixel.cr as usize;
+        let normalize = |value: f32| value.round().clamp(0.0, 255.0) / 255.;
+        [
+            normalize(y + self.red_from_cr[cr]),
+            normalize(y - self.green_from_cb[cb] - self.green_from_cr[cr]),
+            normalize(y + self.blue_from_cb[cb]),
+        ]
     }
 }
 
-fn load_into_scratchpad(scratchpad: &mut [f32], image: &YCbCr422Image) {
-    let mut scratchpad_index = 0;
-    for y in 0..DETECTION_IMAGE_HEIGHT as u32 {
-        for x in
-            DETECTION_IMAGE_START_X as u32..(DETECTION_IMAGE_START_X + DETECTION_IMAGE_WIDTH) as u32
-        {
-            let pixel: Rgb = image.at(x, y).into();
+/// Converts the cropped image into the network input and returns the mean luminance of the
+/// cropped pixels.
+pub fn load_into_scratchpad(
+    scratchpad: &mut [f32],
+    image: &YCbCr422Image,
+    crop: DetectionCrop,
+) -> f32 {
+    let lookup_table = ChromaLookupTable::shared();
+    let input_size = crop.input_size;
+    let (red, green, blue) = channel_planes(scratchpad, input_size);
+    let luminance_sum: u32 = red
+        .chunks_exact_mut(input_size.width)
+        .zip(green.chunks_exact_mut(input_size.width))
+        .zip(blue.chunks_exact_mut(input_size.width))
+        .enumerate()
+        .map(|(y, ((red, green), blue))| load_row(lookup_table, image, crop, y, red, green, blue))
+        .sum();
+    luminance_sum as f32 / input_size.stride() as f32
+}
+
+/// Like [`load_into_scratchpad`], but converts the rows of the crop on `thread_pool`. Every row
+/// writes a disjoint range of each channel, so the rows need no synchronization.
+pub fn load_into_scratchpad_parallel(
+    scratchpad: &mut [f32],
+    image: &YCbCr422Image,
+    crop: DetectionCrop,
+    thread_pool: &ThreadPool,
+) -> f32 {
+    let lookup_table = ChromaLookupTable::shared();
+    let input_size = crop.input_size;
+    let (red, green, blue) = channel_planes(scratchpad, input_size);
+    let luminance_sum: u32 = thread_pool.install(|| {
+        red.par_chunks_exact_mut(input_size.width)
+            .zip(green.par_chunks_exact_mut(input_size.width))
+            .zip(blue.par_chunks_exact_mut(input_size.width))
+            .enumerate()
+            .map(|(y, ((red, green), blue))| {
+                load_row(lookup_table, image, crop, y, red, green, blue)
+            })
+            .sum()
+    });
+    luminance_sum as f32 / input_size.stride() as f32
+}
+
+/// Splits the network input of a single image into its red, green and blue channel.
+fn channel_planes(
+    scratchpad: &mut [f32],
+    input_size: NetworkInputSize,
+) -> (&mut [f32], &mut [f32], &mut [f32]) {
+    let stride = input_size.stride();
+    debug_assert!(
+        scratchpad.len() >= 3 * stride,
+        "scratchpad of size {} is too small for the network input of size {}",
+        scratchpad.len(),
+        input_size.scratchpad_size(),
+    );
+    let (red, rest) = scratchpad.split_at_mut(stride);
+    let (green, rest) = rest.split_at_mut(stride);
+    (red, green, &mut rest[..stride])
+}
+
+/// Converts row `y` of the crop into the rows of the three channels and returns the summed
+/// luminance of its pixels.
+fn load_row(
+    lookup_table: &ChromaLookupTable,
+    image: &YCbCr422Image,
+    crop: DetectionCrop,
+    y: usize,
+    red: &mut [f32],
+    green: &mut [f32],
+    blue: &mut [f32],
+) -> u32 {
+    let mut luminance_sum = 0;
+    for (x, ((red, green), blue)) in red.iter_mut().zip(green).zip(blue).enumerate() {
+        let (image_x, image_y) = crop.to_image(x as f32, y as f32);
+        let pixel = image.at(image_x as u32, image_y as u32);
+        luminance_sum += pixel.y as u32;
+        [*red, *green, *blue] = lookup_table.normalized_rgb(pixel);
+    }
+    luminance_sum
+}
 
-            scratchpad[scratchpad_index] = pixel.r as f32 / 255.;
-            scratchpad[scratchpad_index + STRIDE] = pixel.g as f32 / 255.;
-            scratchpad[scratchpad_index + 2 * STRIDE] = pixel.b as f32 / 255.;
+/// Decides in which cycles inference runs and keeps the poses of the last inference for the
+/// cycles in between.
+#[cfg(feature = "openvino")]
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct InferenceSchedule {
+    /// `None` until the first inference.
+    cycles_since_inference: Option<usize>,
+    cached_poses: Vec<HumanPose>,
+}
 
-            scratchpad_index += 1;
+#[cfg(feature = "openvino")]
+impl InferenceSchedule {
+    fn should_run(&self, inference_period: usize) -> bool {
+        match self.cycles_since_inference {
+            Some(cycles) => cycles + 1 >= inference_period,
+            None => true,
         }
     }
+
+    fn store(&mut self, poses: &[HumanPose]) {
+        self.cycles_since_inference = Some(0);
+        self.cached_poses = poses.to_vec();
+    }
+
+    /// Returns the cached poses for a cycle without inference, or nothing once they are older
+    /// than `maximum_age` skipped cycles.
+    fn skip(&mut self, maximum_age: usize) -> Vec<HumanPose> {
+        let cycles = self.cycles_since_inference.get_or_insert(0);
+        *cycles += 1;
+        if *cycles > maximum_age {
+            self.cached_poses.clear();
+        }
+        self.cached_poses.clone()
+    }
+}
+
+/// Whether any detection score reaches `presence_threshold`. This threshold is usually lower
+/// than the one for poses, so that a person can be present without a detected pose.
+#[cfg(any(feature = "openvino", test))]
+fn person_present(scores: impl IntoIterator<Item = f32>, presence_threshold: f32) -> bool {
+    scores.into_iter().any(|score| score >= presence_threshold)
+}
+
+/// Remembers the bounding box of the most confident detection to crop around it in the following
+/// inferences.
+#[cfg(feature = "openvino")]
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RegionOfInterest {
+    last_detection: Option<(Rectangle<Pixel>, SystemTime)>,
+}
+
+#[cfg(feature = "openvino")]
+impl RegionOfInterest {
+    fn update(&mut self, poses: &[HumanPose], now: SystemTime) {
+        let most_confident_pose = poses
+            .iter()
+            .max_by(|left, right| left.bounding_box.score.total_cmp(&right.bounding_box.score));
+        if let Some(pose) = most_confident_pose {
+            self.last_detection = Some((pose.bounding_box.area, now));
+        }
+    }
+
+    /// The last detected bounding box, unless it is older than `timeout`.
+    fn crop(&self, now: SystemTime, timeout: Duration) -> Option<Rectangle<Pixel>> {
+        let (area, detection_time) = self.last_detection?;
+        let age = now.duration_since(detection_time).unwrap_or_default();
+        (age <= timeout).then_some(area)
+    }
+}
+
+/// Views the raw network output as `(batch, channel, detection)`, keeping only the bounding box
+/// and keypoint channels. Models exported with a segmentation head append mask coefficients
+/// after these, which are ignored.
+#[cfg(any(feature = "openvino", test))]
+fn pose_channels(
+    prediction: &[f32],
+    batch_size: usize,
+    output_channels: usize,
+    values_per_detection: usize,
+    number_of_detections: usize,
+) -> Result<ArrayView3<'_, f32>, PoseDetectionError> {
+    if output_channels < values_per_detection {
+        return Err(PoseDetectionError::OutputChannelMismatch {
+            expected: values_per_detection,
+            actual: output_channels,
+        });
+    }
+    let prediction = ArrayView::from_shape(
+        (batch_size, output_channels, number_of_detections),
+        prediction,
+    )?;
+    Ok(prediction.slice_move(s![.., ..values_per_detection, ..]))
 }
 
+/// Drops poses with too many confident keypoints far outside their bounding box, which usually
+/// are spurious detections.
+#[cfg(feature = "openvino")]
+fn reject_inconsistent_poses(
+    poses: Vec<HumanPose>,
+    keypoint_confidence_threshold: f32,
+    maximum_fraction_of_keypoints_outside: f32,
+) -> Vec<HumanPose> {
+    poses
+        .into_iter()
+        .filter(|pose| {
+            pose.fraction_of_keypoints_outside_bounding_box(
+                keypoint_confidence_threshold,
+                KEYPOINT_BOUNDING_BOX_MARGIN,
+            ) <= maximum_fraction_of_keypoints_outside
+        })
+        .collect()
+}
+
+/// Splits decoded poses into accepted ones scoring at least `acceptance_threshold` and rejected
+/// ones scoring at least `rejection_threshold`. Without a rejection threshold, no poses are
+/// reported as rejected. Poses below both thresholds are dropped.
+#[cfg(any(feature = "openvino", test))]
+fn split_rejected_poses(
+    poses: Vec<HumanPose>,
+    acceptance_threshold: f32,
+    rejection_threshold: Option<f32>,
+) -> (Vec<HumanPose>, Vec<HumanPose>) {
+    let (accepted, rejected): (Vec<_>, Vec<_>) = poses
+        .into_iter()
+        .partition(|pose| pose.bounding_box.score >= acceptance_threshold);
+    let rejected = match rejection_threshold {
+        Some(rejection_threshold) => rejected
+            .into_iter()
+            .filter(|pose| pose.bounding_box.score >= rejection_threshold)
+            .collect(),
+        None => Vec::new(),
+    };
+    (accepted, rejected)
+}
+
+/// Keeps the highest scoring poses and drops the ones overlapping them. Ties in score are broken
+/// deterministically: the larger bounding box wins, then the one further left, then further up.
+/// All bounding boxes have to be in the same coordinate space, i.e. poses of different crops
+/// must be mapped into image coordinates first. The overlap of boxes in different spaces is
+/// meaningless.
+#[cfg(any(feature = "openvino", test))]
 fn non_maximum_suppression(
-    mut candidate_pose: Vec<HumanPose>,
+    candidate_poses: Vec<HumanPose>,
     intersection_over_union_threshold: f32,
+    merge_suppressed_keypoints: bool,
+    use_generalized_intersection_over_union: bool,
+    box_score_weight: f32,
+    score_floor: Option<f32>,
 ) -> Vec<HumanPose> {
-    let mut poses = Vec::new();
-    candidate_pose.sort_unstable_by(|pose1, pose2| {
+    non_maximum_suppression_with(
+        candidate_poses,
+        intersection_over_union_threshold,
+        merge_suppressed_keypoints,
+        box_score_weight,
+        score_floor,
+        |detection, candidate| {
+            if use_generalized_intersection_over_union {
+                detection.generalized_intersection_over_union(candidate)
+            } else {
+                detection.intersection_over_union(candidate)
+            }
+        },
+    )
+}
+
+/// Keeps the best scoring pose and suppresses all remaining ones overlapping it by at least
+/// `intersection_over_union_threshold`, repeated until no candidates are left. Candidates are
+/// sorted once and suppressed in place. Disjoint bounding boxes are never compared, since their
+/// overlap cannot reach a positive threshold. With a `score_floor`, candidates whose combined
+/// score is below it are dropped without being compared at all.
+#[cfg(any(feature = "openvino", test))]
+fn non_maximum_suppression_with(
+    mut candidate_poses: Vec<HumanPose>,
+    intersection_over_union_threshold: f32,
+    merge_suppressed_keypoints: bool,
+    box_score_weight: f32,
+    score_floor: Option<f32>,
+    mut overlap: impl FnMut(&BoundingBox, &BoundingBox) -> f32,
+) -> Vec<HumanPose> {
+    candidate_poses.sort_unstable_by(|pose1, pose2| {
+        let box1 = pose1.bounding_box;
+        let box2 = pose2.bounding_box;
         pose1
-            .bounding_box
-            .score
-            .total_cmp(&pose2.bounding_box.score)
+            .combined_score(box_score_weight)
+            .total_cmp(&pose2.combined_score(box_score_weight))
+            .then(box1.area.area().total_cmp(&box2.area.area()))
+            .then(box2.area.min.x().total_cmp(&box1.area.min.x()))
+            .then(box2.area.min.y().total_cmp(&box1.area.min.y()))
     });
+    if let Some(score_floor) = score_floor {
+        let below_floor = candidate_poses
+            .partition_point(|pose| pose.combined_score(box_score_weight) < score_floor);
+        candidate_poses.drain(..below_floor);
+    }
 
-    while let Some(detection) = candidate_pose.pop() {
-        candidate_pose = candidate_pose
-            .into_iter()
-            .filter(|detection_candidate| {
-                detection
-                    .bounding_box
-                    .intersection_over_union(&detection_candidate.bounding_box)
-                    < intersection_over_union_threshold
-            })
-            .collect_vec();
-
-        poses.push(detection)
+    let mut suppressed = vec![false; candidate_poses.len()];
+    let mut poses = Vec::new();
+    for index in (0..candidate_poses.len()).rev() {
+        if suppressed[index] {
+            continue;
+        }
+        let mut detection = candidate_poses[index];
+        for (candidate, is_suppressed) in candidate_poses[..index]
+            .iter()
+            .zip(&mut suppressed[..index])
+        {
+            if *is_suppressed
+                || (intersection_over_union_threshold > 0.0
+                    && !boxes_intersect(&detection.bounding_box, &candidate.bounding_box))
+            {
+                continue;
+            }
+            if overlap(&detection.bounding_box, &candidate.bounding_box)
+                >= intersection_over_union_threshold
+            {
+                *is_suppressed = true;
+                if merge_suppressed_keypoints {
+                    detection.keypoints = detection
+                        .keypoints
+                        .merge_most_confident(candidate.keypoints);
+                }
+            }
+        }
+        poses.push(detection);
     }
 
     poses
 }
 
+#[cfg(any(feature = "openvino", test))]
+fn boxes_intersect(first: &BoundingBox, second: &BoundingBox) -> bool {
+    first.area.min.x() < second.area.max.x()
+        && second.area.min.x() < first.area.max.x()
+        && first.area.min.y() < second.area.max.y()
+        && second.area.min.y() < first.area.max.y()
+}
+
+#[cfg(feature = "openvino")]
 trait AsBytes {
     fn as_bytes(&self) -> &[u8];
 }
 
+#[cfg(feature = "openvino")]
 impl AsBytes for [f32] {
     fn as_bytes(&self) -> &[u8] {
         unsafe {
@@ -281,3 +1327,586 @@ impl AsBytes for [f32] {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use types::{color::YCbCr422, pose_detection::Keypoint};
+
+    use super::*;
+
+    fn keypoints_with_confidences(confidences: [f32; 17]) -> Keypoints {
+        confidences
+            .map(|confidence| Keypoint {
+                point: point![0.0, 0.0],
+                confidence,
+            })
+            .into()
+    }
+
+    fn pose(center_x: f32, score: f32, keypoints: Keypoints) -> HumanPose {
+        HumanPose::new(
+            BoundingBox {
+                area: Rectangle::new_with_center_and_size(
+                    point![center_x, 100.0],
+                    vector![50.0, 150.0],
+                ),
+                score,
+            },
+            keypoints,
+        )
+    }
+
+    #[test]
+    fn merge_keeps_most_confident_keypoints_of_overlapping_poses() {
+        let mut left_side_visible = [0.0; 17];
+        let mut right_side_visible = [0.0; 17];
+        for index in 0..17 {
+            if index % 2 == 0 {
+                left_side_visible[index] = 0.9;
+                right_side_visible[index] = 0.1;
+            } else {
+                left_side_visible[index] = 0.2;
+                right_side_visible[index] = 0.8;
+            }
+        }
+        let poses = vec![
+            pose(100.0, 0.9, keypoints_with_confidences(left_side_visible)),
+            pose(102.0, 0.7, keypoints_with_confidences(right_side_visible)),
+        ];
+
+        let merged_poses = non_maximum_suppression(poses.clone(), 0.45, true, false, 1.0, None);
+        assert_eq!(merged_poses.len(), 1);
+        assert_eq!(merged_poses[0].bounding_box.score, 0.9);
+        let merged_confidences: [Keypoint; 17] = merged_poses[0].keypoints.into();
+        for (index, keypoint) in merged_confidences.iter().enumerate() {
+            let expected_confidence = if index % 2 == 0 { 0.9 } else { 0.8 };
+            assert_eq!(keypoint.confidence, expected_confidence);
+        }
+
+        let suppressed_poses = non_maximum_suppression(poses, 0.45, false, false, 1.0, None);
+        assert_eq!(suppressed_poses.len(), 1);
+        let confidences: [Keypoint; 17] = suppressed_poses[0].keypoints.into();
+        for (index, keypoint) in confidences.iter().enumerate() {
+            assert_eq!(keypoint.confidence, left_side_visible[index]);
+        }
+    }
+
+    #[test]
+    fn poses_below_the_acceptance_threshold_are_reported_as_rejected() {
+        let keypoints = keypoints_with_confidences([0.9; 17]);
+        let poses = vec![
+            pose(10.0, 0.9, keypoints),
+            pose(20.0, 0.15, keypoints),
+            pose(30.0, 0.05, keypoints),
+            pose(40.0, 0.2, keypoints),
+            pose(50.0, 0.1, keypoints),
+        ];
+        let scores = |poses: &[HumanPose]| {
+            poses
+                .iter()
+                .map(|pose| pose.bounding_box.score)
+                .collect_vec()
+        };
+
+        let (accepted, rejected) = split_rejected_poses(poses.clone(), 0.2, Some(0.1));
+        assert_eq!(scores(&accepted), vec![0.9, 0.2]);
+        assert_eq!(scores(&rejected), vec![0.15, 0.1]);
+
+        let (accepted_unsubscribed, rejected) = split_rejected_poses(poses, 0.2, None);
+        assert_eq!(scores(&accepted_unsubscribed), scores(&accepted));
+        assert!(rejected.is_empty());
+    }
+
+    fn naive_non_maximum_suppression(
+        mut candidate_pose: Vec<HumanPose>,
+        intersection_over_union_threshold: f32,
+        mut overlap: impl FnMut(&BoundingBox, &BoundingBox) -> f32,
+    ) -> Vec<HumanPose> {
+        candidate_pose.sort_unstable_by(|pose1, pose2| {
+            let box1 = pose1.bounding_box;
+            let box2 = pose2.bounding_box;
+            pose1
+                .combined_score(1.0)
+                .total_cmp(&pose2.combined_score(1.0))
+                .then(box1.area.area().total_cmp(&box2.area.area()))
+                .then(box2.area.min.x().total_cmp(&box1.area.min.x()))
+                .then(box2.area.min.y().total_cmp(&box1.area.min.y()))
+        });
+        let mut poses = Vec::new();
+        while let Some(mut detection) = candidate_pose.pop() {
+            let (remaining_candidates, suppressed_poses): (Vec<_>, Vec<_>) =
+                candidate_pose.into_iter().partition(|candidate| {
+                    overlap(&detection.bounding_box, &candidate.bounding_box)
+                        < intersection_over_union_threshold
+                });
+            candidate_pose = remaining_candidates;
+            detection.keypoints =
+                suppressed_poses
+                    .iter()
+                    .fold(detection.keypoints, |keypoints, suppressed_pose| {
+                        keypoints.merge_most_confident(suppressed_pose.keypoints)
+                    });
+            poses.push(detection);
+        }
+        poses
+    }
+
+    #[test]
+    fn non_maximum_suppression_matches_naive_version_with_fewer_overlap_computations() {
+        let poses = (0..200)
+            .map(|index| {
+                let confidences =
+                    std::array::from_fn(|keypoint| ((index * 7 + keypoint * 3) % 10) as f32 / 10.0);
+                pose(
+                    (index % 40) as f32 * 30.0,
+                    ((index * 37) % 100) as f32 / 100.0,
+                    keypoints_with_confidences(confidences),
+                )
+            })
+            .collect_vec();
+        let scores_and_confidences = |poses: &[HumanPose]| {
+            poses
+                .iter()
+                .map(|pose| {
+                    let keypoints: [Keypoint; 17] = pose.keypoints.into();
+                    (
+                        pose.bounding_box.score,
+                        pose.bounding_box.area.min.x(),
+                        keypoints.map(|keypoint| keypoint.confidence),
+                    )
+                })
+                .collect_vec()
+        };
+
+        let mut naive_overlaps = 0;
+        let expected = naive_non_maximum_suppression(poses.clone(), 0.45, |first, second| {
+            naive_overlaps += 1;
+            first.intersection_over_union(second)
+        });
+        let mut overlaps = 0;
+        let suppressed =
+            non_maximum_suppression_with(poses.clone(), 0.45, true, 1.0, None, |first, second| {
+                overlaps += 1;
+                first.intersection_over_union(second)
+            });
+
+        assert_eq!(
+            scores_and_confidences(&suppressed),
+            scores_and_confidences(&expected)
+        );
+        assert!(overlaps < naive_overlaps);
+
+        let mut floored_overlaps = 0;
+        let floored =
+            non_maximum_suppression_with(poses, 0.45, true, 1.0, Some(0.5), |first, second| {
+                floored_overlaps += 1;
+                first.intersection_over_union(second)
+            });
+        assert!(floored_overlaps < overlaps);
+        assert!(!floored.is_empty());
+        assert!(floored.iter().all(|pose| pose.bounding_box.score >= 0.5));
+    }
+
+    #[test]
+    fn crop_offset_is_clamped_to_image() {
+        assert_eq!(crop_start_x(0, 640, 192), 224);
+        assert_eq!(crop_start_x(-100, 640, 192), 124);
+        assert_eq!(crop_start_x(100, 640, 192), 324);
+        assert_eq!(crop_start_x(-1000, 640, 192), 0);
+        assert_eq!(crop_start_x(1000, 640, 192), 640 - 192);
+        assert_eq!(crop_start_x(0, 640, 320), 160);
+    }
+
+    #[test]
+    fn zoomed_crop_maps_back_into_center_of_standard_crop() {
+        let standard = DetectionCrop::standard(NetworkInputSize::default(), 224);
+        let zoomed = DetectionCrop::zoomed(NetworkInputSize::default(), 224);
+
+        let (standard_center_x, _) = standard.to_image(
+            DETECTION_IMAGE_WIDTH as f32 / 2.0,
+            DETECTION_IMAGE_HEIGHT as f32 / 2.0,
+        );
+        let (zoomed_center_x, zoomed_center_y) = zoomed.to_image(
+            DETECTION_IMAGE_WIDTH as f32 / 2.0,
+            DETECTION_IMAGE_HEIGHT as f32 / 2.0,
+        );
+        assert_eq!(zoomed_center_x, standard_center_x);
+        assert_eq!(zoomed_center_y, DETECTION_IMAGE_HEIGHT as f32 / 4.0);
+
+        let (zoomed_end_x, zoomed_end_y) =
+            zoomed.to_image(DETECTION_IMAGE_WIDTH as f32, DETECTION_IMAGE_HEIGHT as f32);
+        assert_eq!(zoomed_end_x, 224.0 + 0.75 * DETECTION_IMAGE_WIDTH as f32);
+        assert_eq!(zoomed_end_y, DETECTION_IMAGE_HEIGHT as f32 / 2.0);
+    }
+
+    #[test]
+    fn person_is_present_below_the_pose_threshold() {
+        let pose_threshold = 0.5;
+        let presence_threshold = 0.25;
+        let scores = [0.1, 0.3, 0.2];
+
+        assert!(scores.iter().all(|&score| score < pose_threshold));
+        assert!(person_present(scores, presence_threshold));
+        assert!(!person_present(scores, 0.35));
+        assert!(!person_present([], presence_threshold));
+    }
+
+    #[test]
+    fn region_of_interest_crop_covers_expanded_box() {
+        let area = Rectangle::new_with_center_and_size(point![300.0, 200.0], vector![60.0, 170.0]);
+
+        let crop_area =
+            DetectionCrop::around(NetworkInputSize::default(), area, 0.2, 640, 480).area();
+
+        assert!(crop_area.min.x() <= 300.0 - 42.0 && crop_area.max.x() >= 300.0 + 42.0);
+        assert!(crop_area.min.y() <= 200.0 - 119.0 && crop_area.max.y() >= 200.0 + 119.0);
+        assert!(crop_area.max.y() - crop_area.min.y() < DETECTION_IMAGE_HEIGHT as f32);
+    }
+
+    #[test]
+    fn region_of_interest_crop_stays_inside_image() {
+        let area = Rectangle::new_with_center_and_size(point![630.0, 400.0], vector![100.0, 300.0]);
+
+        let crop_area =
+            DetectionCrop::around(NetworkInputSize::default(), area, 0.2, 640, 480).area();
+
+        assert!(crop_area.min.x() >= 0.0 && crop_area.max.x() <= 640.0);
+        assert!(crop_area.min.y() >= 0.0 && crop_area.max.y() <= 480.0);
+    }
+
+    #[cfg(feature = "openvino")]
+    #[test]
+    fn region_of_interest_expires_without_detections() {
+        let mut region_of_interest = RegionOfInterest::default();
+        let start = SystemTime::UNIX_EPOCH;
+        let timeout = Duration::from_secs(1);
+        let detection = pose(300.0, 0.9, keypoints_with_confidences([1.0; 17]));
+
+        assert_eq!(region_of_interest.crop(start, timeout), None);
+        region_of_interest.update(&[detection], start);
+        region_of_interest.update(&[], start + Duration::from_millis(500));
+
+        assert_eq!(
+            region_of_interest.crop(start + Duration::from_millis(900), timeout),
+            Some(detection.bounding_box.area)
+        );
+        assert_eq!(
+            region_of_interest.crop(start + Duration::from_secs(2), timeout),
+            None
+        );
+    }
+
+    #[test]
+    fn equal_scores_are_resolved_independent_of_input_order() {
+        let smaller = pose(100.0, 0.8, keypoints_with_confidences([0.1; 17]));
+        let mut larger = pose(100.0, 0.8, keypoints_with_confidences([0.2; 17]));
+        larger.bounding_box.area =
+            Rectangle::new_with_center_and_size(point![100.0, 100.0], vector![60.0, 160.0]);
+        let left = pose(95.0, 0.8, keypoints_with_confidences([0.3; 17]));
+        let right = pose(105.0, 0.8, keypoints_with_confidences([0.4; 17]));
+
+        for poses in [vec![smaller, larger], vec![larger, smaller]] {
+            let survivors = non_maximum_suppression(poses, 0.45, false, false, 1.0, None);
+            assert_eq!(survivors.len(), 1);
+            assert_eq!(survivors[0].keypoints.nose.confidence, 0.2);
+        }
+        for poses in [vec![left, right], vec![right, left]] {
+            let survivors = non_maximum_suppression(poses, 0.45, false, false, 1.0, None);
+            assert_eq!(survivors.len(), 1);
+            assert_eq!(survivors[0].keypoints.nose.confidence, 0.3);
+        }
+    }
+
+    #[test]
+    fn merge_ignores_non_overlapping_poses() {
+        let poses = vec![
+            pose(100.0, 0.9, keypoints_with_confidences([0.1; 17])),
+            pose(400.0, 0.7, keypoints_with_confidences([0.8; 17])),
+        ];
+
+        let merged_poses = non_maximum_suppression(poses, 0.45, true, false, 1.0, None);
+        assert_eq!(merged_poses.len(), 2);
+        let confidences: [Keypoint; 17] = merged_poses[0].keypoints.into();
+        assert!(confidences
+            .iter()
+            .all(|keypoint| keypoint.confidence == 0.1));
+    }
+
+    #[test]
+    fn chroma_lookup_table_matches_rgb_conversion() {
+        let lookup_table = ChromaLookupTable::new();
+        for y in (0..=255).step_by(5) {
+            for cb in (0..=255).step_by(3) {
+                for cr in (0..=255).step_by(3) {
+                    let pixel = YCbCr444 { y, cb, cr };
+                    let rgb: types::color::Rgb = pixel.into();
+                    assert_eq!(
+                        lookup_table.normalized_rgb(pixel),
+                        [rgb.r, rgb.g, rgb.b].map(|value| value as f32 / 255.),
+                        "{pixel:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    fn uniform_image(luminance: u8) -> YCbCr422Image {
+        let pixel = YCbCr422 {
+            y1: luminance,
+            cb: 128,
+            y2: luminance,
+            cr: 128,
+        };
+        YCbCr422Image::from_ycbcr_buffer(320, 480, vec![pixel; 320 * 480])
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "is too small for the network input")]
+    fn undersized_scratchpad_is_rejected() {
+        let input_size = NetworkInputSize::default();
+        let mut scratchpad = vec![0.0; input_size.scratchpad_size() - 1];
+
+        load_into_scratchpad(
+            &mut scratchpad,
+            &uniform_image(128),
+            DetectionCrop::standard(input_size, 224),
+        );
+    }
+
+    #[test]
+    fn parallel_preprocessing_matches_serial_preprocessing() {
+        let buffer = (0..320 * 480)
+            .map(|index| YCbCr422 {
+                y1: (index * 7 % 256) as u8,
+                cb: (index * 13 % 256) as u8,
+                y2: (index * 17 % 256) as u8,
+                cr: (index * 23 % 256) as u8,
+            })
+            .collect();
+        let image = YCbCr422Image::from_ycbcr_buffer(320, 480, buffer);
+        let input_size = NetworkInputSize::default();
+        let thread_pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+
+        for crop in [
+            DetectionCrop::standard(input_size, 224),
+            DetectionCrop::zoomed(input_size, 224),
+        ] {
+            let mut serial = vec![0.0; input_size.scratchpad_size()];
+            let mut parallel = vec![0.0; input_size.scratchpad_size()];
+
+            let serial_luminance = load_into_scratchpad(&mut serial, &image, crop);
+            let parallel_luminance =
+                load_into_scratchpad_parallel(&mut parallel, &image, crop, &thread_pool);
+
+            assert_eq!(serial_luminance, parallel_luminance);
+            assert_eq!(serial, parallel);
+        }
+    }
+
+    #[test]
+    fn crops_follow_the_discovered_input_size() {
+        let input_size = NetworkInputSize {
+            height: 320,
+            width: 320,
+        };
+        let crop = DetectionCrop::standard(input_size, crop_start_x(0, 640, input_size.width));
+        let mut scratchpad = vec![0.0; input_size.scratchpad_size()];
+
+        let mean_luminance = load_into_scratchpad(&mut scratchpad, &uniform_image(96), crop);
+
+        assert_eq!(crop.area().min, point![160.0, 0.0]);
+        assert_eq!(crop.area().max, point![480.0, 320.0]);
+        assert_eq!(mean_luminance, 96.0);
+    }
+
+    #[test]
+    fn score_threshold_is_lowered_for_dim_images() {
+        let adaptation = LuminanceAdaptation {
+            reference_luminance: 128.0,
+            minimum_factor: 0.5,
+        };
+        let input_size = NetworkInputSize::default();
+        let mut scratchpad = vec![0.0; input_size.scratchpad_size()];
+
+        let bright = load_into_scratchpad(
+            &mut scratchpad,
+            &uniform_image(200),
+            DetectionCrop::standard(input_size, 224),
+        );
+        let medium = load_into_scratchpad(
+            &mut scratchpad,
+            &uniform_image(96),
+            DetectionCrop::zoomed(input_size, 224),
+        );
+        let dim = load_into_scratchpad(
+            &mut scratchpad,
+            &uniform_image(40),
+            DetectionCrop::standard(input_size, 224),
+        );
+
+        assert_eq!([bright, medium, dim], [200.0, 96.0, 40.0]);
+        assert_eq!(adaptation.threshold_factor(bright), 1.0);
+        assert_eq!(adaptation.threshold_factor(medium), 0.75);
+        assert_eq!(adaptation.threshold_factor(dim), 0.5);
+
+        let disabled = LuminanceAdaptation {
+            reference_luminance: 128.0,
+            minimum_factor: 1.0,
+        };
+        assert_eq!(disabled.threshold_factor(dim), 1.0);
+    }
+
+    #[test]
+    fn trailing_mask_channels_are_ignored() {
+        let batch_size = 2;
+        let values_per_detection = 56;
+        let mask_channels = 32;
+        let output_channels = values_per_detection + mask_channels;
+        let number_of_detections = 1890;
+        let buffer = (0..batch_size * output_channels * number_of_detections)
+            .map(|index| {
+                let channel = index / number_of_detections % output_channels;
+                if channel < values_per_detection {
+                    index as f32
+                } else {
+                    -1.0
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let prediction = pose_channels(
+            &buffer,
+            batch_size,
+            output_channels,
+            values_per_detection,
+            number_of_detections,
+        )
+        .unwrap();
+
+        assert_eq!(
+            prediction.shape(),
+            [batch_size, values_per_detection, number_of_detections]
+        );
+        assert!(prediction.iter().all(|&value| value >= 0.0));
+        assert_eq!(
+            prediction[[1, 4, 7]],
+            ((output_channels + 4) * number_of_detections + 7) as f32
+        );
+    }
+
+    #[test]
+    fn too_few_output_channels_are_rejected() {
+        let buffer = vec![0.0; 50 * 1890];
+
+        assert!(matches!(
+            pose_channels(&buffer, 1, 50, 56, 1890),
+            Err(PoseDetectionError::OutputChannelMismatch {
+                expected: 56,
+                actual: 50
+            })
+        ));
+    }
+
+    #[test]
+    fn input_size_is_discovered_from_rgb_inputs() {
+        assert_eq!(
+            input_size_from_dimensions(&[1, 3, 480, 192]).unwrap(),
+            NetworkInputSize::default()
+        );
+        assert_eq!(
+            input_size_from_dimensions(&[2, 3, 640, 640]).unwrap(),
+            NetworkInputSize {
+                height: 640,
+                width: 640
+            }
+        );
+        assert_eq!(NetworkInputSize::default().number_of_detections(), 1890);
+        assert_eq!(
+            NetworkInputSize {
+                height: 640,
+                width: 640
+            }
+            .number_of_detections(),
+            8400
+        );
+
+        for dimensions in [
+            &[1, 480, 192, 3][..],
+            &[1, 1, 480, 192],
+            &[1, 3, 0, 192],
+            &[3, 480, 192],
+        ] {
+            assert!(matches!(
+                input_size_from_dimensions(dimensions),
+                Err(PoseDetectionError::InputShapeMismatch { actual, .. }) if actual == dimensions
+            ));
+        }
+    }
+
+    #[cfg(feature = "openvino")]
+    #[test]
+    fn inference_is_skipped_and_cached_poses_age_out() {
+        let mut schedule = InferenceSchedule::default();
+        let poses = vec![pose(100.0, 0.9, keypoints_with_confidences([1.0; 17]))];
+
+        assert!(schedule.should_run(3));
+        schedule.store(&poses);
+
+        let mut ran = Vec::new();
+        let mut number_of_returned_poses = Vec::new();
+        for _ in 0..6 {
+            if schedule.should_run(3) {
+                ran.push(true);
+                schedule.store(&poses);
+                number_of_returned_poses.push(poses.len());
+            } else {
+                ran.push(false);
+                number_of_returned_poses.push(schedule.skip(1).len());
+            }
+        }
+
+        assert_eq!(ran, [false, false, true, false, false, true]);
+        assert_eq!(number_of_returned_poses, [1, 0, 1, 1, 0, 1]);
+        assert!(InferenceSchedule::default().should_run(1));
+        assert!(schedule.should_run(1));
+    }
+
+    #[test]
+    fn crop_areas_cover_network_input() {
+        let standard = DetectionCrop::standard(NetworkInputSize::default(), 224).area();
+        let zoomed = DetectionCrop::zoomed(NetworkInputSize::default(), 224).area();
+
+        assert_eq!(standard.min, point![224.0, 0.0]);
+        assert_eq!(
+            standard.max,
+            point![
+                224.0 + DETECTION_IMAGE_WIDTH as f32,
+                DETECTION_IMAGE_HEIGHT as f32
+            ]
+        );
+        assert_eq!(zoomed.min, point![224.0 + 48.0, 0.0]);
+        assert_eq!(
+            zoomed.max,
+            point![
+                224.0 + 48.0 + DETECTION_IMAGE_WIDTH as f32 * ZOOMED_CROP_SCALE,
+                DETECTION_IMAGE_HEIGHT as f32 * ZOOMED_CROP_SCALE
+            ]
+        );
+    }
+
+    #[test]
+    fn keypoint_confidences_can_change_the_surviving_pose() {
+        let high_box_score = pose(100.0, 0.9, keypoints_with_confidences([0.2; 17]));
+        let crisp_keypoints = pose(105.0, 0.8, keypoints_with_confidences([0.9; 17]));
+        let poses = vec![high_box_score, crisp_keypoints];
+
+        let survivors = non_maximum_suppression(poses.clone(), 0.45, false, false, 1.0, None);
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].bounding_box.score, 0.9);
+
+        let survivors = non_maximum_suppression(poses, 0.45, false, false, 0.5, None);
+        assert_eq!(survivors.len(), 1);
+        assert_eq!(survivors[0].bounding_box.score, 0.8);
+    }
+}
@@ -0,0 +1,208 @@
+use std::{
+    fs::{create_dir_all, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use types::pose_detection::HumanPose;
+
+/// Version of the records written by [`PoseLogger`]. Records without a version were written
+/// before versioning was introduced and are version 1.
+///
+/// Fields added to [`HumanPose`] have to default when missing so that older records still
+/// deserialize. Changes that cannot be expressed like this need a new version and a migration in
+/// [`PoseLogRecord`]'s `TryFrom<VersionedPoseLogRecord>`.
+pub const POSE_LOG_VERSION: u32 = 2;
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(try_from = "VersionedPoseLogRecord")]
+pub struct PoseLogRecord {
+    pub version: u32,
+    pub timestamp: SystemTime,
+    pub poses: Vec<HumanPose>,
+}
+
+#[derive(Deserialize)]
+struct VersionedPoseLogRecord {
+    #[serde(default = "first_version")]
+    version: u32,
+    timestamp: SystemTime,
+    poses: Vec<HumanPose>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+#[derive(Debug, Error)]
+#[error("pose log record has version {version}, newest supported is {POSE_LOG_VERSION}")]
+pub struct UnsupportedPoseLogVersion {
+    version: u32,
+}
+
+impl TryFrom<VersionedPoseLogRecord> for PoseLogRecord {
+    type Error = UnsupportedPoseLogVersion;
+
+    fn try_from(record: VersionedPoseLogRecord) -> Result<Self, Self::Error> {
+        match record.version {
+            // Version 1 only lacks the version tag.
+            1 | POSE_LOG_VERSION => Ok(Self {
+                version: POSE_LOG_VERSION,
+                timestamp: record.timestamp,
+                poses: record.poses,
+            }),
+            version => Err(UnsupportedPoseLogVersion { version }),
+        }
+    }
+}
+
+/// Appends one JSON record per line. Writes are buffered and only reach the file once the buffer
+/// is full or the logger is dropped.
+pub struct PoseLogger {
+    writer: BufWriter<std::fs::File>,
+}
+
+impl PoseLogger {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(directory) = path.parent() {
+            create_dir_all(directory)
+                .wrap_err_with(|| format!("failed to create pose log directory {directory:?}"))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .wrap_err_with(|| format!("failed to open pose log {path:?}"))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn log(&mut self, timestamp: SystemTime, poses: &[HumanPose]) -> Result<()> {
+        serde_json::to_writer(
+            &mut self.writer,
+            &PoseLogRecord {
+                version: POSE_LOG_VERSION,
+                timestamp,
+                poses: poses.to_vec(),
+            },
+        )
+        .wrap_err("failed to serialize pose log record")?;
+        writeln!(self.writer).wrap_err("failed to write pose log record")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::read_to_string, time::Duration};
+
+    use geometry::rectangle::Rectangle;
+    use linear_algebra::{point, vector};
+    use types::{bounding_box::BoundingBox, pose_detection::Keypoints};
+
+    use super::*;
+
+    #[test]
+    fn logged_records_can_be_read_back() {
+        let path = std::env::temp_dir()
+            .join(format!("pose_log_tests_{}", std::process::id()))
+            .join("human_poses.jsonl");
+        let pose = HumanPose::new(
+            BoundingBox {
+                area: Rectangle::new_with_center_and_size(
+                    point![320.0, 240.0],
+                    vector![50.0, 150.0],
+                ),
+                score: 0.9,
+            },
+            Keypoints::try_new(&[0.5; 51], 0.0, 0.0).unwrap(),
+        );
+        let first_timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let second_timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(2);
+
+        {
+            let mut logger = PoseLogger::open(&path).unwrap();
+            logger.log(first_timestamp, &[pose]).unwrap();
+            logger.log(second_timestamp, &[]).unwrap();
+        }
+
+        let records: Vec<PoseLogRecord> = read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].version, POSE_LOG_VERSION);
+        assert_eq!(records[0].timestamp, first_timestamp);
+        assert_eq!(records[0].poses.len(), 1);
+        assert_eq!(records[0].poses[0].bounding_box.score, 0.9);
+        assert_eq!(records[1].timestamp, second_timestamp);
+        assert!(records[1].poses.is_empty());
+    }
+
+    #[test]
+    fn version_1_records_are_migrated() {
+        let keypoint = r#"{ "point": [1.0, 2.0], "confidence": 0.5 }"#;
+        let keypoints = [
+            "left_eye",
+            "right_eye",
+            "nose",
+            "left_ear",
+            "right_ear",
+            "left_shoulder",
+            "right_shoulder",
+            "left_hand",
+            "right_hand",
+            "left_elbow",
+            "right_elbow",
+            "left_hip",
+            "right_hip",
+            "left_knee",
+            "right_knee",
+            "left_foot",
+            "right_foot",
+        ]
+        .map(|name| format!(r#""{name}": {keypoint}"#))
+        .join(", ");
+        let record = format!(
+            r#"{{
+                "timestamp": {{ "secs_since_epoch": 1, "nanos_since_epoch": 0 }},
+                "poses": [{{
+                    "bounding_box": {{
+                        "area": {{ "min": [295.0, 165.0], "max": [345.0, 315.0] }},
+                        "score": 0.9
+                    }},
+                    "keypoints": {{ {keypoints} }}
+                }}]
+            }}"#
+        );
+
+        let record: PoseLogRecord = serde_json::from_str(&record).unwrap();
+
+        assert_eq!(record.version, POSE_LOG_VERSION);
+        assert_eq!(
+            record.timestamp,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1)
+        );
+        assert_eq!(record.poses[0].bounding_box.score, 0.9);
+        assert_eq!(record.poses[0].keypoints.nose.confidence, 0.5);
+    }
+
+    #[test]
+    fn records_of_newer_versions_are_rejected() {
+        let record = r#"{
+            "version": 3,
+            "timestamp": { "secs_since_epoch": 1, "nanos_since_epoch": 0 },
+            "poses": []
+        }"#;
+
+        let error = serde_json::from_str::<PoseLogRecord>(record).unwrap_err();
+
+        assert!(error.to_string().contains("version 3"));
+    }
+}
@@ -1,5 +1,6 @@
 use coordinate_systems::Pixel;
 use geometry::rectangle::Rectangle;
+use linear_algebra::{point, Point2};
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 
@@ -10,10 +11,143 @@ pub struct BoundingBox {
 }
 
 impl BoundingBox {
+    /// Maps the box from pixel coordinates into coordinates relative to the image size, where the
+    /// image spans from 0.0 to 1.0 in both directions. The intersection over union of two boxes
+    /// is the same in both spaces, as long as both boxes are in the same one.
+    pub fn to_normalized(&self, image_width: f32, image_height: f32) -> Self {
+        self.scaled(1.0 / image_width, 1.0 / image_height)
+    }
+
+    /// Inverse of [`Self::to_normalized`].
+    pub fn from_normalized(&self, image_width: f32, image_height: f32) -> Self {
+        self.scaled(image_width, image_height)
+    }
+
+    fn scaled(&self, factor_x: f32, factor_y: f32) -> Self {
+        let scale = |point: Point2<Pixel>| point![point.x() * factor_x, point.y() * factor_y];
+        Self {
+            area: Rectangle {
+                min: scale(self.area.min),
+                max: scale(self.area.max),
+            },
+            score: self.score,
+        }
+    }
+
     pub fn intersection_over_union(&self, other: &Self) -> f32 {
         let intersection = self.area.rectangle_intersection(other.area);
         let union = self.area.area() + other.area.area();
 
         intersection / (union - intersection)
     }
+
+    /// Like [`Self::intersection_over_union`], but reduced by the fraction of the smallest
+    /// enclosing box not covered by either box. This is negative for disjoint boxes and shrinks
+    /// further the farther apart they are.
+    pub fn generalized_intersection_over_union(&self, other: &Self) -> f32 {
+        let intersection = self.area.rectangle_intersection(other.area);
+        let union = self.area.area() + other.area.area() - intersection;
+        let enclosing = Rectangle::<Pixel> {
+            min: point![
+                f32::min(self.area.min.x(), other.area.min.x()),
+                f32::min(self.area.min.y(), other.area.min.y())
+            ],
+            max: point![
+                f32::max(self.area.max.x(), other.area.max.x()),
+                f32::max(self.area.max.y(), other.area.max.y())
+            ],
+        }
+        .area();
+
+        intersection / union - (enclosing - union) / enclosing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use linear_algebra::vector;
+
+    use super::*;
+
+    fn bounding_box(center_x: f32) -> BoundingBox {
+        BoundingBox {
+            area: Rectangle::new_with_center_and_size(point![center_x, 0.0], vector![2.0, 2.0]),
+            score: 1.0,
+        }
+    }
+
+    #[test]
+    fn generalized_intersection_over_union_equals_intersection_over_union_for_same_box() {
+        let box1 = bounding_box(0.0);
+
+        assert_relative_eq!(box1.intersection_over_union(&box1), 1.0);
+        assert_relative_eq!(box1.generalized_intersection_over_union(&box1), 1.0);
+    }
+
+    #[test]
+    fn generalized_intersection_over_union_is_zero_for_touching_boxes() {
+        let box1 = bounding_box(0.0);
+        let box2 = bounding_box(2.0);
+
+        assert_relative_eq!(box1.intersection_over_union(&box2), 0.0);
+        assert_relative_eq!(box1.generalized_intersection_over_union(&box2), 0.0);
+    }
+
+    #[test]
+    fn generalized_intersection_over_union_decreases_with_distance() {
+        let box1 = bounding_box(0.0);
+        let near = bounding_box(4.0);
+        let far = bounding_box(10.0);
+
+        assert_relative_eq!(box1.intersection_over_union(&near), 0.0);
+        assert_relative_eq!(box1.intersection_over_union(&far), 0.0);
+        assert_relative_eq!(box1.generalized_intersection_over_union(&near), -1.0 / 3.0);
+        assert_relative_eq!(box1.generalized_intersection_over_union(&far), -2.0 / 3.0);
+    }
+
+    #[test]
+    fn normalized_bounding_box_converts_back_to_pixels() {
+        let bounding_box = BoundingBox {
+            area: Rectangle {
+                min: point![64.0, 120.0],
+                max: point![320.0, 480.0],
+            },
+            score: 0.7,
+        };
+
+        let normalized = bounding_box.to_normalized(640.0, 480.0);
+        assert_relative_eq!(normalized.area.min, point![0.1, 0.25]);
+        assert_relative_eq!(normalized.area.max, point![0.5, 1.0]);
+        assert_eq!(normalized.score, 0.7);
+
+        let pixels = normalized.from_normalized(640.0, 480.0);
+        assert_relative_eq!(pixels.area.min, bounding_box.area.min);
+        assert_relative_eq!(pixels.area.max, bounding_box.area.max);
+    }
+
+    #[test]
+    fn intersection_over_union_is_invariant_under_normalization() {
+        let box1 = BoundingBox {
+            area: Rectangle::new_with_center_and_size(point![100.0, 200.0], vector![80.0, 200.0]),
+            score: 1.0,
+        };
+        let box2 = BoundingBox {
+            area: Rectangle::new_with_center_and_size(point![140.0, 260.0], vector![60.0, 160.0]),
+            score: 1.0,
+        };
+        let normalized1 = box1.to_normalized(640.0, 480.0);
+        let normalized2 = box2.to_normalized(640.0, 480.0);
+
+        assert_relative_eq!(
+            box1.intersection_over_union(&box2),
+            normalized1.intersection_over_union(&normalized2),
+            epsilon = 1e-6
+        );
+        assert_relative_eq!(
+            box1.generalized_intersection_over_union(&box2),
+            normalized1.generalized_intersection_over_union(&normalized2),
+            epsilon = 1e-6
+        );
+    }
 }
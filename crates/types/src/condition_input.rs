@@ -2,10 +2,19 @@ use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
 
-use crate::fall_state::FallState;
+use crate::{
+    cycle_time::CycleTime, fall_state::FallState, joints::Joints, primary_state::PrimaryState,
+    sensor_data::ForceSensitiveResistors,
+};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, SerializeHierarchy)]
 pub struct ConditionInput {
+    pub angular_velocity: Vector3<f32>,
     pub filtered_angular_velocity: Vector3<f32>,
+    pub linear_acceleration: Vector3<f32>,
     pub fall_state: FallState,
+    pub cycle_time: CycleTime,
+    pub primary_state: PrimaryState,
+    pub positions: Joints<f32>,
+    pub force_sensitive_resistors: ForceSensitiveResistors,
 }
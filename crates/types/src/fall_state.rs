@@ -9,6 +9,17 @@ pub enum Side {
     Right,
 }
 
+impl Side {
+    /// Side the robot is tilted towards, a positive roll tilts it to the right.
+    pub fn from_roll(roll: f32) -> Self {
+        if roll > 0.0 {
+            Side::Right
+        } else {
+            Side::Left
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
 pub enum Direction {
     Forward { side: Side },
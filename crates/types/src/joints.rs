@@ -315,6 +315,19 @@ impl Mul<f32> for Joints<f32> {
     }
 }
 
+/// Multiplies each joint with the corresponding joint of `right`.
+impl Mul for Joints<f32> {
+    type Output = Joints<f32>;
+
+    fn mul(self, right: Self) -> Self::Output {
+        let mut product = self;
+        for (joint, factor) in right.enumerate() {
+            product[joint] *= factor;
+        }
+        product
+    }
+}
+
 impl Div<f32> for Joints<f32> {
     type Output = Joints<f32>;
 
@@ -57,6 +57,9 @@ pub enum MotionCommand {
         kicking_side: Side,
         strength: f32,
     },
+    Wave {
+        head: HeadMotion,
+    },
 }
 
 impl MotionCommand {
@@ -66,7 +69,8 @@ impl MotionCommand {
             | MotionCommand::Initial { head, .. }
             | MotionCommand::Stand { head, .. }
             | MotionCommand::Walk { head, .. }
-            | MotionCommand::InWalkKick { head, .. } => Some(*head),
+            | MotionCommand::InWalkKick { head, .. }
+            | MotionCommand::Wave { head } => Some(*head),
             MotionCommand::Penalized => Some(HeadMotion::ZeroAngles),
             MotionCommand::Unstiff => Some(HeadMotion::Unstiff),
             MotionCommand::ArmsUpSquat
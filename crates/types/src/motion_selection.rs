@@ -25,6 +25,7 @@ pub enum MotionType {
     StandUpSitting,
     Unstiff,
     Walk,
+    Wave,
 }
 
 impl Default for MotionType {
@@ -49,6 +50,7 @@ pub struct MotionSafeExits {
     stand: bool,
     unstiff: bool,
     walk: bool,
+    wave: bool,
 }
 
 impl Default for MotionSafeExits {
@@ -68,10 +70,18 @@ impl Default for MotionSafeExits {
             stand: true,
             unstiff: true,
             walk: false,
+            wave: false,
         }
     }
 }
 
+impl MotionSafeExits {
+    /// Marks whether the motion can be exited.
+    pub fn set(&mut self, motion_type: MotionType, safe_to_exit: bool) {
+        self[motion_type] = safe_to_exit;
+    }
+}
+
 impl Index<MotionType> for MotionSafeExits {
     type Output = bool;
 
@@ -91,6 +101,7 @@ impl Index<MotionType> for MotionSafeExits {
             MotionType::StandUpSitting => &self.stand_up_sitting,
             MotionType::Unstiff => &self.unstiff,
             MotionType::Walk => &self.walk,
+            MotionType::Wave => &self.wave,
         }
     }
 }
@@ -112,6 +123,22 @@ impl IndexMut<MotionType> for MotionSafeExits {
             MotionType::StandUpSitting => &mut self.stand_up_sitting,
             MotionType::Unstiff => &mut self.unstiff,
             MotionType::Walk => &mut self.walk,
+            MotionType::Wave => &mut self.wave,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_marks_motion_safe_to_exit() {
+        let mut motion_safe_exits = MotionSafeExits::default();
+
+        motion_safe_exits.set(MotionType::StandUpFront, false);
+        motion_safe_exits.set(MotionType::StandUpFront, true);
+
+        assert!(motion_safe_exits[MotionType::StandUpFront]);
+    }
+}
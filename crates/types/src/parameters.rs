@@ -21,6 +21,17 @@ pub struct WhistleDetectionParameters {
     pub number_of_chunks: usize,
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
+pub struct LineTrackingParameters {
+    /// Maximum angle in radians between a fitted line and the track it updates.
+    pub maximum_angle_difference: f32,
+    /// Maximum distance of the end points of a fitted line and its track from the other line.
+    pub maximum_distance: f32,
+    /// Weight of a fitted line when updating its track, 1.0 disables the filtering.
+    pub smoothing_factor: f32,
+    pub maximum_missed_cycles: usize,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
 pub struct StepPlannerParameters {
     pub injected_step: Option<Step>,
@@ -43,6 +54,13 @@ pub struct BehaviorParameters {
     pub look_action: LookActionParameters,
     pub intercept_ball: InterceptBallParameters,
     pub initial_lookaround_duration: Duration,
+    pub stand_up_facing_down_pitch_threshold: f32,
+    /// How far the pitch has to be beyond the threshold to override the kind of fall detected
+    /// by the fall state estimation.
+    pub stand_up_pitch_override_margin: f32,
+    /// Number of consecutive cycles the robot has to be fallen before it stands up.
+    pub stand_up_minimum_fallen_cycles: usize,
+    pub wave_at_referee: bool,
 }
 
 #[derive(Copy, Clone, Debug, Default, Deserialize, Serialize, SerializeHierarchy)]
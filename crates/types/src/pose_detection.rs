@@ -3,9 +3,74 @@ use std::ops::Index;
 use crate::bounding_box::BoundingBox;
 use color_eyre::Result;
 use coordinate_systems::Pixel;
+use geometry::rectangle::Rectangle;
 use linear_algebra::{point, Point2};
 use serde::{Deserialize, Serialize};
 use serialize_hierarchy::SerializeHierarchy;
+use thiserror::Error;
+
+pub const NUMBER_OF_KEYPOINTS: usize = 17;
+const VALUES_PER_KEYPOINT: usize = 3;
+
+/// Below this ratio of horizontal shoulder distance to bounding box width, a pose is seen in
+/// profile.
+const PROFILE_SHOULDER_WIDTH_RATIO: f32 = 0.2;
+
+#[derive(Debug, Error)]
+pub enum KeypointsError {
+    #[error("expected {expected} keypoint values, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("keypoint layout refers to index {model_index}, but the model only has {number_of_keypoints} keypoints")]
+    InvalidLayoutIndex {
+        model_index: usize,
+        number_of_keypoints: usize,
+    },
+}
+
+/// Maps the keypoints a model outputs to [`Keypoints`]. `model_indices` has one entry per field of
+/// [`Keypoints`] in declaration order, naming which of the model's `number_of_keypoints` outputs
+/// holds it. Keypoints the model does not provide are `None` and get zero confidence.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct KeypointLayout {
+    pub number_of_keypoints: usize,
+    pub model_indices: [Option<usize>; NUMBER_OF_KEYPOINTS],
+}
+
+impl KeypointLayout {
+    /// The 17 keypoint layout of models trained on COCO.
+    pub fn coco() -> Self {
+        Self {
+            number_of_keypoints: NUMBER_OF_KEYPOINTS,
+            model_indices: std::array::from_fn(Some),
+        }
+    }
+
+    /// Number of values per detection, each keypoint consists of x, y and confidence.
+    pub fn number_of_values(&self) -> usize {
+        self.number_of_keypoints * VALUES_PER_KEYPOINT
+    }
+
+    pub fn validate(&self) -> Result<(), KeypointsError> {
+        match self
+            .model_indices
+            .iter()
+            .flatten()
+            .find(|&&model_index| model_index >= self.number_of_keypoints)
+        {
+            Some(&model_index) => Err(KeypointsError::InvalidLayoutIndex {
+                model_index,
+                number_of_keypoints: self.number_of_keypoints,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for KeypointLayout {
+    fn default() -> Self {
+        Self::coco()
+    }
+}
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, SerializeHierarchy)]
 pub struct Keypoint {
@@ -35,31 +100,120 @@ pub struct Keypoints {
 }
 
 impl Keypoints {
-    pub fn try_new(keypoints_slice: &[f32], x_offset: f32, y_offset: f32) -> Option<Self> {
-        let mut keypoints_iter = keypoints_slice.chunks(3).map(|keypoint_chunk| Keypoint {
-            point: point![keypoint_chunk[0] + x_offset, keypoint_chunk[1] + y_offset],
-            confidence: keypoint_chunk[2],
-        });
-
-        Some(Self {
-            left_eye: keypoints_iter.next()?,
-            right_eye: keypoints_iter.next()?,
-            nose: keypoints_iter.next()?,
-            left_ear: keypoints_iter.next()?,
-            right_ear: keypoints_iter.next()?,
-            left_shoulder: keypoints_iter.next()?,
-            right_shoulder: keypoints_iter.next()?,
-            left_hand: keypoints_iter.next()?,
-            right_hand: keypoints_iter.next()?,
-            left_elbow: keypoints_iter.next()?,
-            right_elbow: keypoints_iter.next()?,
-            left_hip: keypoints_iter.next()?,
-            right_hip: keypoints_iter.next()?,
-            left_knee: keypoints_iter.next()?,
-            right_knee: keypoints_iter.next()?,
-            left_foot: keypoints_iter.next()?,
-            right_foot: keypoints_iter.next()?,
-        })
+    /// Builds keypoints from `[x, y, confidence]` triples in the COCO layout.
+    pub fn try_new(
+        keypoints_slice: &[f32],
+        x_offset: f32,
+        y_offset: f32,
+    ) -> Result<Self, KeypointsError> {
+        Self::try_new_with_layout(keypoints_slice, &KeypointLayout::coco(), x_offset, y_offset)
+    }
+
+    /// Builds keypoints from `[x, y, confidence]` triples, one per keypoint of the model.
+    pub fn try_new_with_layout(
+        keypoints_slice: &[f32],
+        layout: &KeypointLayout,
+        x_offset: f32,
+        y_offset: f32,
+    ) -> Result<Self, KeypointsError> {
+        let expected = layout.number_of_values();
+        if keypoints_slice.len() != expected {
+            return Err(KeypointsError::InvalidLength {
+                expected,
+                actual: keypoints_slice.len(),
+            });
+        }
+        layout.validate()?;
+        let keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] =
+            layout.model_indices.map(|model_index| match model_index {
+                Some(model_index) => {
+                    let keypoint_chunk = &keypoints_slice[model_index * VALUES_PER_KEYPOINT..];
+                    Keypoint {
+                        point: point![keypoint_chunk[0] + x_offset, keypoint_chunk[1] + y_offset],
+                        confidence: keypoint_chunk[2],
+                    }
+                }
+                None => Keypoint {
+                    point: point![x_offset, y_offset],
+                    confidence: 0.0,
+                },
+            });
+
+        Ok(keypoints.into())
+    }
+
+    pub fn merge_most_confident(self, other: Keypoints) -> Keypoints {
+        let mut merged_keypoints: [Keypoint; 17] = self.into();
+        let other_keypoints: [Keypoint; 17] = other.into();
+        for (merged_keypoint, other_keypoint) in merged_keypoints.iter_mut().zip(other_keypoints) {
+            if other_keypoint.confidence > merged_keypoint.confidence {
+                *merged_keypoint = other_keypoint;
+            }
+        }
+        merged_keypoints.into()
+    }
+
+    /// Interpolates from these keypoints at `t = 0.0` towards the newer `other` at `t = 1.0`.
+    /// Only keypoints visible in both, i.e. reaching `keypoint_confidence_threshold`, are
+    /// interpolated. All others take the value of `other`, since an occluded keypoint has no
+    /// meaningful position to interpolate from.
+    pub fn lerp(&self, other: &Keypoints, t: f32, keypoint_confidence_threshold: f32) -> Keypoints {
+        let keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] = (*self).into();
+        let other_keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] = (*other).into();
+        let mut other_keypoints = other_keypoints.into_iter();
+        keypoints
+            .map(|keypoint| {
+                let other_keypoint = other_keypoints.next().expect("one keypoint per keypoint");
+                let is_visible =
+                    |keypoint: &Keypoint| keypoint.confidence >= keypoint_confidence_threshold;
+                if !is_visible(&keypoint) || !is_visible(&other_keypoint) {
+                    return other_keypoint;
+                }
+                Keypoint {
+                    point: keypoint.point + (other_keypoint.point - keypoint.point) * t,
+                    confidence: keypoint.confidence
+                        + (other_keypoint.confidence - keypoint.confidence) * t,
+                }
+            })
+            .into()
+    }
+
+    /// Maps the keypoints from pixel coordinates into coordinates relative to the image size,
+    /// where the image spans from 0.0 to 1.0 in both directions.
+    pub fn to_normalized(self, image_width: f32, image_height: f32) -> Keypoints {
+        self.scaled(1.0 / image_width, 1.0 / image_height)
+    }
+
+    /// Inverse of [`Self::to_normalized`].
+    pub fn from_normalized(self, image_width: f32, image_height: f32) -> Keypoints {
+        self.scaled(image_width, image_height)
+    }
+
+    /// Maps the keypoints into coordinates relative to `bounding_box`, where the box spans from
+    /// 0.0 to 1.0 in both directions. Poses of the same gesture then have the same keypoints
+    /// regardless of their distance to the camera.
+    pub fn normalized_within(self, bounding_box: &Rectangle<Pixel>) -> Keypoints {
+        let size = bounding_box.max - bounding_box.min;
+        let keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] = self.into();
+        keypoints
+            .map(|keypoint| {
+                let offset = keypoint.point - bounding_box.min;
+                Keypoint {
+                    point: point![offset.x() / size.x(), offset.y() / size.y()],
+                    confidence: keypoint.confidence,
+                }
+            })
+            .into()
+    }
+
+    fn scaled(self, factor_x: f32, factor_y: f32) -> Keypoints {
+        let keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] = self.into();
+        keypoints
+            .map(|keypoint| Keypoint {
+                point: point![keypoint.point.x() * factor_x, keypoint.point.y() * factor_y],
+                confidence: keypoint.confidence,
+            })
+            .into()
     }
 }
 impl Index<usize> for Keypoints {
@@ -111,6 +265,40 @@ impl From<Keypoints> for [Keypoint; 17] {
         ]
     }
 }
+impl From<[Keypoint; 17]> for Keypoints {
+    fn from(keypoints: [Keypoint; 17]) -> Self {
+        let [left_eye, right_eye, nose, left_ear, right_ear, left_shoulder, right_shoulder, left_hand, right_hand, left_elbow, right_elbow, left_hip, right_hip, left_knee, right_knee, left_foot, right_foot] =
+            keypoints;
+        Self {
+            left_eye,
+            right_eye,
+            nose,
+            left_ear,
+            right_ear,
+            left_shoulder,
+            right_shoulder,
+            left_hand,
+            right_hand,
+            left_elbow,
+            right_elbow,
+            left_hip,
+            right_hip,
+            left_knee,
+            right_knee,
+            left_foot,
+            right_foot,
+        }
+    }
+}
+
+/// Direction a person is facing as seen from the camera. `Left` and `Right` refer to the image.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy)]
+pub enum Facing {
+    Toward,
+    Away,
+    Left,
+    Right,
+}
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, SerializeHierarchy)]
 pub struct HumanPose {
@@ -125,6 +313,206 @@ impl HumanPose {
             keypoints,
         }
     }
+
+    /// The bounding box restricted to an image of the given size.
+    pub fn bounding_box_in_image(&self, image_width: f32, image_height: f32) -> BoundingBox {
+        BoundingBox {
+            area: self.bounding_box.area.clamp(Rectangle {
+                min: point![0.0, 0.0],
+                max: point![image_width, image_height],
+            }),
+            score: self.bounding_box.score,
+        }
+    }
+
+    /// Fraction of the keypoints with at least `keypoint_confidence_threshold` confidence lying
+    /// outside the bounding box enlarged by `margin` times its size on each side. Zero if no
+    /// keypoint is confident enough.
+    pub fn fraction_of_keypoints_outside_bounding_box(
+        &self,
+        keypoint_confidence_threshold: f32,
+        margin: f32,
+    ) -> f32 {
+        let area = self.bounding_box.area;
+        let enlargement = (area.max - area.min) * margin;
+        let enlarged_area = Rectangle {
+            min: area.min - enlargement,
+            max: area.max + enlargement,
+        };
+        let keypoints: [Keypoint; 17] = self.keypoints.into();
+        let (number_of_confident, number_of_outside) = keypoints
+            .iter()
+            .filter(|keypoint| keypoint.confidence >= keypoint_confidence_threshold)
+            .fold((0, 0), |(confident, outside), keypoint| {
+                let point = keypoint.point;
+                let is_inside = (enlarged_area.min.x()..=enlarged_area.max.x())
+                    .contains(&point.x())
+                    && (enlarged_area.min.y()..=enlarged_area.max.y()).contains(&point.y());
+                (confident + 1, outside + usize::from(!is_inside))
+            });
+        if number_of_confident == 0 {
+            return 0.0;
+        }
+        number_of_outside as f32 / number_of_confident as f32
+    }
+
+    pub fn mean_keypoint_confidence(&self) -> f32 {
+        let keypoints: [Keypoint; NUMBER_OF_KEYPOINTS] = self.keypoints.into();
+        keypoints
+            .iter()
+            .map(|keypoint| keypoint.confidence)
+            .sum::<f32>()
+            / NUMBER_OF_KEYPOINTS as f32
+    }
+
+    /// Blends the bounding box score with the mean keypoint confidence, weighting the bounding
+    /// box score by `box_score_weight`. A weight of 1.0 yields the bounding box score.
+    pub fn combined_score(&self, box_score_weight: f32) -> f32 {
+        box_score_weight * self.bounding_box.score
+            + (1.0 - box_score_weight) * self.mean_keypoint_confidence()
+    }
+
+    /// Estimates the facing direction from the shoulder ordering, or from the nose position if
+    /// the shoulders overlap in profile. Returns `None` if not both shoulders are visible or the
+    /// nose is needed but not visible.
+    pub fn facing(&self, keypoint_confidence_threshold: f32) -> Option<Facing> {
+        let Keypoints {
+            left_shoulder,
+            right_shoulder,
+            nose,
+            ..
+        } = self.keypoints;
+        if left_shoulder.confidence < keypoint_confidence_threshold
+            || right_shoulder.confidence < keypoint_confidence_threshold
+        {
+            return None;
+        }
+
+        let shoulder_distance_x = left_shoulder.point.x() - right_shoulder.point.x();
+        let bounding_box_width = self.bounding_box.area.max.x() - self.bounding_box.area.min.x();
+        if shoulder_distance_x.abs() >= PROFILE_SHOULDER_WIDTH_RATIO * bounding_box_width {
+            // a person facing the camera has their left shoulder on the right side of the image
+            return Some(if shoulder_distance_x > 0.0 {
+                Facing::Toward
+            } else {
+                Facing::Away
+            });
+        }
+
+        if nose.confidence < keypoint_confidence_threshold {
+            return None;
+        }
+        let shoulder_center_x = (left_shoulder.point.x() + right_shoulder.point.x()) / 2.0;
+        Some(if nose.point.x() < shoulder_center_x {
+            Facing::Left
+        } else {
+            Facing::Right
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
+pub struct ScoreCalibrationPoint {
+    pub raw: f32,
+    pub calibrated: f32,
+}
+
+/// Piecewise-linear mapping from raw detection scores to calibrated ones. The support points
+/// are sorted by raw score. Between them, scores are interpolated linearly, beyond them the
+/// calibrated score of the outermost support point is used. Without support points, scores are
+/// kept as they are.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
+pub struct ScoreCalibration {
+    pub support_points: Vec<ScoreCalibrationPoint>,
+}
+
+impl ScoreCalibration {
+    pub fn calibrate(&self, raw: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.support_points.first(), self.support_points.last())
+        else {
+            return raw;
+        };
+        if raw <= first.raw {
+            return first.calibrated;
+        }
+        self.support_points
+            .windows(2)
+            .find(|window| raw <= window[1].raw)
+            .map_or(last.calibrated, |window| {
+                let (lower, upper) = (window[0], window[1]);
+                let t = (raw - lower.raw) / (upper.raw - lower.raw);
+                lower.calibrated + t * (upper.calibrated - lower.calibrated)
+            })
+    }
+}
+
+/// Lowers the detection score threshold in dim images, where the scores are systematically
+/// lower. The threshold is scaled by the ratio of the image's mean luminance to
+/// `reference_luminance`, but not below `minimum_factor` and not above 1.0. A `minimum_factor`
+/// of 1.0 disables the adaptation.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
+pub struct LuminanceAdaptation {
+    pub reference_luminance: f32,
+    pub minimum_factor: f32,
+}
+
+impl LuminanceAdaptation {
+    pub fn threshold_factor(&self, mean_luminance: f32) -> f32 {
+        (mean_luminance / self.reference_luminance)
+            .min(1.0)
+            .max(self.minimum_factor)
+    }
+}
+
+/// Coordinate system of emitted keypoints. `Normalized` keypoints are relative to the full image
+/// size, with the image spanning from 0.0 to 1.0 in both directions.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy,
+)]
+pub enum KeypointSpace {
+    #[default]
+    Pixel,
+    Normalized,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, SerializeHierarchy,
+)]
+pub enum EdgePenaltyMode {
+    #[default]
+    Drop,
+    DownWeight,
+}
+
+/// Penalizes detections whose bounding box comes closer than `margin` times its size to the
+/// border of the network input. Such detections are often clipped and show people only
+/// partially. They are either dropped or their score is multiplied by `score_factor`.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, SerializeHierarchy)]
+pub struct EdgePenalty {
+    pub margin: f32,
+    pub mode: EdgePenaltyMode,
+    pub score_factor: f32,
+}
+
+impl EdgePenalty {
+    /// Returns the penalized score of a detection within `bounds`, or `None` if it is dropped.
+    pub fn penalized_score(
+        &self,
+        area: Rectangle<Pixel>,
+        bounds: Rectangle<Pixel>,
+        score: f32,
+    ) -> Option<f32> {
+        let margin = (area.max - area.min) * self.margin;
+        let touches_edge = area.min.x() - bounds.min.x() < margin.x()
+            || bounds.max.x() - area.max.x() < margin.x()
+            || area.min.y() - bounds.min.y() < margin.y()
+            || bounds.max.y() - area.max.y() < margin.y();
+        match (touches_edge, self.mode) {
+            (false, _) => Some(score),
+            (true, EdgePenaltyMode::Drop) => None,
+            (true, EdgePenaltyMode::DownWeight) => Some(score * self.score_factor),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, SerializeHierarchy)]
@@ -132,3 +520,376 @@ pub struct RefereePoseCandidate {
     pub pose: HumanPose,
     pub distance_to_referee_position: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use linear_algebra::vector;
+
+    use super::*;
+
+    #[test]
+    fn boxes_clipped_at_the_edge_are_penalized() {
+        let bounds = Rectangle {
+            min: point![224.0, 0.0],
+            max: point![416.0, 480.0],
+        };
+        let clipped =
+            Rectangle::new_with_center_and_size(point![400.0, 240.0], vector![40.0, 200.0]);
+        let inside =
+            Rectangle::new_with_center_and_size(point![320.0, 240.0], vector![40.0, 200.0]);
+        let mut edge_penalty = EdgePenalty {
+            margin: 0.1,
+            mode: EdgePenaltyMode::Drop,
+            score_factor: 0.5,
+        };
+
+        assert_eq!(edge_penalty.penalized_score(clipped, bounds, 0.8), None);
+        assert_eq!(edge_penalty.penalized_score(inside, bounds, 0.8), Some(0.8));
+
+        edge_penalty.mode = EdgePenaltyMode::DownWeight;
+        assert_eq!(
+            edge_penalty.penalized_score(clipped, bounds, 0.8),
+            Some(0.4)
+        );
+        assert_eq!(edge_penalty.penalized_score(inside, bounds, 0.8), Some(0.8));
+    }
+
+    #[test]
+    fn bounding_box_near_right_edge_is_clamped() {
+        let keypoints = Keypoints::try_new(&[0.0; 51], 0.0, 0.0).unwrap();
+        let pose = HumanPose::new(
+            BoundingBox {
+                area: Rectangle::new_with_center_and_size(
+                    point![620.0, 240.0],
+                    vector![60.0, 500.0],
+                ),
+                score: 0.8,
+            },
+            keypoints,
+        );
+
+        let bounding_box = pose.bounding_box_in_image(640.0, 480.0);
+
+        assert_eq!(bounding_box.area.min, point![590.0, 0.0]);
+        assert_eq!(bounding_box.area.max, point![640.0, 480.0]);
+        assert_eq!(bounding_box.score, 0.8);
+    }
+
+    fn pose_with_keypoints(
+        left_shoulder_x: f32,
+        right_shoulder_x: f32,
+        nose: Option<f32>,
+    ) -> HumanPose {
+        let mut keypoints = Keypoints::try_new(&[0.0; 51], 0.0, 0.0).unwrap();
+        keypoints.left_shoulder = Keypoint {
+            point: point![left_shoulder_x, 100.0],
+            confidence: 0.9,
+        };
+        keypoints.right_shoulder = Keypoint {
+            point: point![right_shoulder_x, 100.0],
+            confidence: 0.9,
+        };
+        if let Some(nose_x) = nose {
+            keypoints.nose = Keypoint {
+                point: point![nose_x, 80.0],
+                confidence: 0.9,
+            };
+        }
+        HumanPose::new(
+            BoundingBox {
+                area: Rectangle::new_with_center_and_size(
+                    point![100.0, 150.0],
+                    vector![100.0, 200.0],
+                ),
+                score: 0.9,
+            },
+            keypoints,
+        )
+    }
+
+    #[test]
+    fn frontal_poses_face_toward_or_away() {
+        assert_eq!(
+            pose_with_keypoints(130.0, 70.0, Some(100.0)).facing(0.5),
+            Some(Facing::Toward)
+        );
+        assert_eq!(
+            pose_with_keypoints(70.0, 130.0, None).facing(0.5),
+            Some(Facing::Away)
+        );
+    }
+
+    #[test]
+    fn profile_poses_face_toward_nose() {
+        assert_eq!(
+            pose_with_keypoints(102.0, 98.0, Some(80.0)).facing(0.5),
+            Some(Facing::Left)
+        );
+        assert_eq!(
+            pose_with_keypoints(98.0, 102.0, Some(120.0)).facing(0.5),
+            Some(Facing::Right)
+        );
+        assert_eq!(pose_with_keypoints(98.0, 102.0, None).facing(0.5), None);
+    }
+
+    #[test]
+    fn facing_needs_both_shoulders() {
+        let mut pose = pose_with_keypoints(130.0, 70.0, Some(100.0));
+        pose.keypoints.right_shoulder.confidence = 0.1;
+
+        assert_eq!(pose.facing(0.5), None);
+    }
+
+    #[test]
+    fn keypoints_with_wrong_length_are_rejected() {
+        let error = Keypoints::try_new(&[0.0; 50], 0.0, 0.0).unwrap_err();
+
+        assert!(matches!(
+            error,
+            KeypointsError::InvalidLength {
+                expected: 51,
+                actual: 50
+            }
+        ));
+    }
+
+    #[test]
+    fn keypoints_follow_custom_layout() {
+        let mut model_indices = [None; NUMBER_OF_KEYPOINTS];
+        model_indices[5] = Some(1);
+        model_indices[6] = Some(0);
+        let layout = KeypointLayout {
+            number_of_keypoints: 2,
+            model_indices,
+        };
+
+        let keypoints =
+            Keypoints::try_new_with_layout(&[1.0, 2.0, 0.9, 3.0, 4.0, 0.8], &layout, 0.0, 0.0)
+                .unwrap();
+
+        assert_eq!(keypoints.left_shoulder.point, point![3.0, 4.0]);
+        assert_eq!(keypoints.left_shoulder.confidence, 0.8);
+        assert_eq!(keypoints.right_shoulder.point, point![1.0, 2.0]);
+        assert_eq!(keypoints.right_shoulder.confidence, 0.9);
+        assert_eq!(keypoints.nose.confidence, 0.0);
+        assert!(matches!(
+            Keypoints::try_new(&[0.0; 6], 0.0, 0.0),
+            Err(KeypointsError::InvalidLength { .. })
+        ));
+    }
+
+    #[test]
+    fn layout_indices_must_be_within_model_keypoints() {
+        let mut layout = KeypointLayout::coco();
+        layout.number_of_keypoints = 16;
+
+        assert!(matches!(
+            layout.validate(),
+            Err(KeypointsError::InvalidLayoutIndex {
+                model_index: 16,
+                number_of_keypoints: 16
+            })
+        ));
+    }
+
+    #[test]
+    fn keypoints_are_offset() {
+        let values: Vec<f32> = (0..51).map(|value| value as f32).collect();
+
+        let keypoints = Keypoints::try_new(&values, 10.0, 20.0).unwrap();
+
+        assert_eq!(keypoints.left_eye.point, point![10.0, 21.0]);
+        assert_eq!(keypoints.left_eye.confidence, 2.0);
+        assert_eq!(keypoints.right_foot.point, point![58.0, 69.0]);
+        assert_eq!(keypoints.right_foot.confidence, 50.0);
+    }
+
+    #[test]
+    fn keypoints_scattered_outside_bounding_box_are_counted() {
+        let mut keypoints: [Keypoint; 17] =
+            Keypoints::try_new(&[0.0; 51], 0.0, 0.0).unwrap().into();
+        for (index, keypoint) in keypoints.iter_mut().enumerate() {
+            let is_scattered = index % 2 == 0;
+            keypoint.point = if is_scattered {
+                point![400.0 + 10.0 * index as f32, 20.0]
+            } else {
+                point![100.0, 100.0 + index as f32]
+            };
+            keypoint.confidence = 0.9;
+        }
+        keypoints[16].confidence = 0.1;
+        let pose = HumanPose::new(
+            BoundingBox {
+                area: Rectangle::new_with_center_and_size(
+                    point![100.0, 100.0],
+                    vector![40.0, 100.0],
+                ),
+                score: 0.8,
+            },
+            keypoints.into(),
+        );
+
+        assert_eq!(
+            pose.fraction_of_keypoints_outside_bounding_box(0.5, 0.1),
+            8.0 / 16.0
+        );
+        assert_eq!(
+            pose.fraction_of_keypoints_outside_bounding_box(0.95, 0.1),
+            0.0
+        );
+    }
+
+    #[test]
+    fn score_calibration_interpolates_between_support_points() {
+        let calibration = ScoreCalibration {
+            support_points: vec![
+                ScoreCalibrationPoint {
+                    raw: 0.2,
+                    calibrated: 0.0,
+                },
+                ScoreCalibrationPoint {
+                    raw: 0.4,
+                    calibrated: 0.6,
+                },
+                ScoreCalibrationPoint {
+                    raw: 0.8,
+                    calibrated: 1.0,
+                },
+            ],
+        };
+
+        assert_eq!(calibration.calibrate(0.0), 0.0);
+        assert_eq!(calibration.calibrate(0.2), 0.0);
+        assert_relative_eq!(calibration.calibrate(0.3), 0.3);
+        assert_relative_eq!(calibration.calibrate(0.6), 0.8);
+        assert_eq!(calibration.calibrate(0.8), 1.0);
+        assert_eq!(calibration.calibrate(0.9), 1.0);
+
+        let calibrated_scores: Vec<_> = (0..=100)
+            .map(|raw| calibration.calibrate(raw as f32 / 100.0))
+            .collect();
+        assert!(calibrated_scores
+            .windows(2)
+            .all(|window| window[0] <= window[1]));
+    }
+
+    #[test]
+    fn score_calibration_without_support_points_is_identity() {
+        let calibration = ScoreCalibration::default();
+
+        assert_eq!(calibration.calibrate(0.0), 0.0);
+        assert_eq!(calibration.calibrate(0.42), 0.42);
+        assert_eq!(calibration.calibrate(1.0), 1.0);
+    }
+
+    fn keypoints_at(offset: f32, confidence: f32) -> Keypoints {
+        std::array::from_fn(|index| Keypoint {
+            point: point![offset + index as f32, 2.0 * offset],
+            confidence,
+        })
+        .into()
+    }
+
+    #[test]
+    fn visible_keypoints_are_interpolated() {
+        let older = keypoints_at(0.0, 0.6);
+        let newer = keypoints_at(10.0, 0.8);
+
+        let interpolated: [Keypoint; NUMBER_OF_KEYPOINTS] = older.lerp(&newer, 0.25, 0.5).into();
+
+        for (index, keypoint) in interpolated.iter().enumerate() {
+            assert_relative_eq!(
+                keypoint.point,
+                point![2.5 + index as f32, 5.0],
+                epsilon = 1e-5
+            );
+            assert_relative_eq!(keypoint.confidence, 0.65, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn keypoints_visible_in_only_one_pose_keep_the_newer_value() {
+        let mut older = keypoints_at(0.0, 0.9);
+        older.nose.confidence = 0.1;
+        let mut newer = keypoints_at(10.0, 0.9);
+        newer.left_foot.confidence = 0.2;
+
+        let interpolated = older.lerp(&newer, 0.5, 0.5);
+
+        assert_eq!(interpolated.nose.point, newer.nose.point);
+        assert_eq!(interpolated.nose.confidence, 0.9);
+        assert_eq!(interpolated.left_foot.point, newer.left_foot.point);
+        assert_eq!(interpolated.left_foot.confidence, 0.2);
+        assert_relative_eq!(interpolated.left_eye.point, point![5.0, 10.0]);
+    }
+
+    #[test]
+    fn interpolation_ends_at_both_keypoints() {
+        let older = keypoints_at(0.0, 0.6);
+        let newer = keypoints_at(10.0, 0.8);
+
+        let start: [Keypoint; NUMBER_OF_KEYPOINTS] = older.lerp(&newer, 0.0, 0.5).into();
+        let end: [Keypoint; NUMBER_OF_KEYPOINTS] = older.lerp(&newer, 1.0, 0.5).into();
+        let older: [Keypoint; NUMBER_OF_KEYPOINTS] = older.into();
+        let newer: [Keypoint; NUMBER_OF_KEYPOINTS] = newer.into();
+
+        for index in 0..NUMBER_OF_KEYPOINTS {
+            assert_eq!(start[index].point, older[index].point);
+            assert_eq!(start[index].confidence, older[index].confidence);
+            assert_eq!(end[index].point, newer[index].point);
+            assert_eq!(end[index].confidence, newer[index].confidence);
+        }
+    }
+
+    #[test]
+    fn keypoints_normalized_within_bounding_box_are_scale_invariant() {
+        let near_box = Rectangle {
+            min: point![100.0, 50.0],
+            max: point![200.0, 350.0],
+        };
+        let far_box = Rectangle {
+            min: point![400.0, 100.0],
+            max: point![425.0, 175.0],
+        };
+        let near: Keypoints = std::array::from_fn(|index| Keypoint {
+            point: point![100.0 + 5.0 * index as f32, 50.0 + 15.0 * index as f32],
+            confidence: 0.8,
+        })
+        .into();
+        let far: Keypoints = std::array::from_fn(|index| Keypoint {
+            point: point![400.0 + 1.25 * index as f32, 100.0 + 3.75 * index as f32],
+            confidence: 0.8,
+        })
+        .into();
+
+        let near: [Keypoint; NUMBER_OF_KEYPOINTS] = near.normalized_within(&near_box).into();
+        let far: [Keypoint; NUMBER_OF_KEYPOINTS] = far.normalized_within(&far_box).into();
+
+        assert_relative_eq!(near[0].point, point![0.0, 0.0]);
+        assert_relative_eq!(near[16].point, point![0.8, 0.8]);
+        for (near, far) in near.iter().zip(far) {
+            assert_relative_eq!(near.point, far.point, epsilon = 1e-5);
+            assert_eq!(near.confidence, far.confidence);
+        }
+    }
+
+    #[test]
+    fn normalized_keypoints_convert_back_to_pixels() {
+        let keypoints: Keypoints = std::array::from_fn(|index| Keypoint {
+            point: point![40.0 * index as f32, 480.0 - 20.0 * index as f32],
+            confidence: index as f32 / 16.0,
+        })
+        .into();
+
+        let normalized = keypoints.to_normalized(640.0, 480.0);
+        assert_relative_eq!(normalized.left_eye.point, point![0.0, 1.0]);
+        assert_relative_eq!(normalized.right_foot.point, point![1.0, 0.3333333]);
+
+        let pixels = normalized.from_normalized(640.0, 480.0);
+        for index in 0..NUMBER_OF_KEYPOINTS {
+            assert_relative_eq!(pixels[index].point, keypoints[index].point, epsilon = 1e-4);
+            assert_eq!(pixels[index].confidence, keypoints[index].confidence);
+        }
+    }
+}
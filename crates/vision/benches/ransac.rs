@@ -0,0 +1,42 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use linear_algebra::{point, Point2};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use vision::Ransac;
+
+struct SomeFrame;
+
+/// Three noisy field lines with outliers, roughly the number of edge points of a camera image.
+fn representative_points() -> Vec<Point2<SomeFrame>> {
+    let mut random_number_generator = StdRng::seed_from_u64(0);
+    let mut points = Vec::new();
+    for (slope, intercept) in [(0.0, 1.0), (0.5, -1.0), (-2.0, 3.0)] {
+        points.extend((0..300).map(|index| {
+            let x = index as f32 * 0.02;
+            let noise = random_number_generator.gen_range(-0.01..0.01);
+            point![x, slope * x + intercept + noise]
+        }));
+    }
+    points.extend((0..200).map(|_| {
+        point![
+            random_number_generator.gen_range(0.0..6.0),
+            random_number_generator.gen_range(-4.0..4.0)
+        ]
+    }));
+    points
+}
+
+fn ransac_benchmark(criterion: &mut Criterion) {
+    let points = representative_points();
+
+    criterion.bench_function("ransac next_line", |bencher| {
+        bencher.iter(|| Ransac::new_seeded(black_box(points.clone()), 0).next_line(20, 0.05, 0.05))
+    });
+    criterion.bench_function("ransac next_line_segment", |bencher| {
+        bencher.iter(|| {
+            Ransac::new_seeded(black_box(points.clone()), 0).next_line_segment(20, 0.05, 0.05)
+        })
+    });
+}
+
+criterion_group!(benches, ransac_benchmark);
+criterion_main!(benches);
@@ -33,6 +33,7 @@ pub struct CycleContext {
         Parameter<usize, "field_border_detection.$cycler_instance.min_points_per_line">,
     second_line_association_distance:
         Parameter<f32, "field_border_detection.$cycler_instance.second_line_association_distance">,
+    ransac_seed: Parameter<Option<u64>, "vision.$cycler_instance.ransac_seed?">,
 
     camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
     image_segments: Input<ImageSegments, "image_segments">,
@@ -72,7 +73,10 @@ impl FieldBorderDetection {
         context
             .field_border_points
             .fill_if_subscribed(|| first_field_pixels.clone());
-        let ransac = Ransac::new(first_field_pixels);
+        let ransac = match context.ransac_seed {
+            Some(seed) => Ransac::new_seeded(first_field_pixels, *seed),
+            None => Ransac::new(first_field_pixels),
+        };
         let border_lines = find_border_lines(
             ransac,
             context.camera_matrix,
@@ -7,6 +7,10 @@ pub mod image_receiver;
 pub mod image_segmenter;
 pub mod limb_projector;
 pub mod line_detection;
+pub mod line_tracking;
 pub mod perspective_grid_candidates_provider;
 mod ransac;
 pub mod segment_filter;
+
+/// Exposed for the RANSAC benchmark.
+pub use ransac::Ransac;
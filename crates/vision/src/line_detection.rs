@@ -1,26 +1,33 @@
-use std::{collections::HashSet, iter::Peekable, ops::Range};
+use std::{collections::HashSet, iter::Peekable, ops::Range, time::Duration};
 
 use color_eyre::Result;
 use geometry::line::{Line, Line2};
 use serde::{Deserialize, Serialize};
 
 use context_attribute::context;
-use coordinate_systems::{Ground, Pixel};
+use coordinate_systems::{Field, Ground, Pixel};
 use framework::{AdditionalOutput, MainOutput};
-use linear_algebra::{distance, point, vector, Point2, Vector2};
+use linear_algebra::{distance, point, vector, Isometry2, Point2, Vector2};
 use ordered_float::NotNan;
 use projection::{camera_matrix::CameraMatrix, Projection};
 use types::{
     filtered_segments::FilteredSegments,
     image_segments::{EdgeType, Segment},
     line_data::{LineData, LineDiscardReason},
+    parameters::LineTrackingParameters,
     ycbcr422_image::YCbCr422Image,
 };
 
-use crate::ransac::{Ransac, RansacResult};
+use crate::{
+    line_tracking::LineTracker,
+    ransac::{Ransac, RansacResult},
+};
 
 #[derive(Deserialize, Serialize)]
-pub struct LineDetection {}
+pub struct LineDetection {
+    line_tracker: LineTracker,
+    last_ground_to_field: Option<Isometry2<Ground, Field>>,
+}
 
 #[context]
 pub struct CreationContext {}
@@ -31,6 +38,7 @@ pub struct CycleContext {
     discarded_lines: AdditionalOutput<Vec<(Line2<Pixel>, LineDiscardReason)>, "discarded_lines">,
     ransac_input: AdditionalOutput<Vec<Point2<Pixel>>, "ransac_input">,
 
+    enable: Parameter<bool, "line_detection.$cycler_instance.enable">,
     allowed_line_length_in_field:
         Parameter<Range<f32>, "line_detection.$cycler_instance.allowed_line_length_in_field">,
     check_edge_gradient: Parameter<bool, "line_detection.$cycler_instance.check_edge_gradient">,
@@ -55,9 +63,18 @@ pub struct CycleContext {
     minimum_number_of_points_on_line:
         Parameter<usize, "line_detection.$cycler_instance.minimum_number_of_points_on_line">,
     ransac_iterations: Parameter<usize, "line_detection.$cycler_instance.ransac_iterations">,
+    ransac_seed: Parameter<Option<u64>, "vision.$cycler_instance.ransac_seed?">,
+    ransac_time_budget:
+        Parameter<Option<Duration>, "line_detection.$cycler_instance.ransac_time_budget?">,
+    /// Grid cell size for downsampling the line points before fitting, disabled if `None`.
+    ransac_downsampling_cell_size:
+        Parameter<Option<f32>, "line_detection.$cycler_instance.ransac_downsampling_cell_size?">,
+    line_tracking:
+        Parameter<LineTrackingParameters, "line_detection.$cycler_instance.line_tracking">,
 
     camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
     filtered_segments: Input<FilteredSegments, "filtered_segments">,
+    ground_to_field: Input<Option<Isometry2<Ground, Field>>, "Control", "ground_to_field?">,
     image: Input<YCbCr422Image, "image">,
 }
 
@@ -65,14 +82,32 @@ pub struct CycleContext {
 #[derive(Default)]
 pub struct MainOutputs {
     pub line_data: MainOutput<Option<LineData>>,
+    /// Lines of this and previous cycles, associated and filtered across cycles.
+    pub tracked_lines: MainOutput<Vec<Line2<Ground>>>,
 }
 
 impl LineDetection {
     pub fn new(_context: CreationContext) -> Result<Self> {
-        Ok(Self {})
+        Ok(Self {
+            line_tracker: LineTracker::default(),
+            last_ground_to_field: None,
+        })
     }
 
     pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        if !context.enable {
+            self.line_tracker = LineTracker::default();
+            self.last_ground_to_field = None;
+            return Ok(MainOutputs {
+                line_data: Some(LineData {
+                    lines: vec![],
+                    used_segments: HashSet::new(),
+                })
+                .into(),
+                tracked_lines: Vec::new().into(),
+            });
+        }
+
         let mut image_lines = Vec::new();
         let mut discarded_lines = Vec::new();
 
@@ -96,7 +131,14 @@ impl LineDetection {
                 .collect()
         });
 
-        let mut ransac = Ransac::new(line_points);
+        let ransac = match context.ransac_seed {
+            Some(seed) => Ransac::new_seeded(line_points, *seed),
+            None => Ransac::new(line_points),
+        };
+        let mut ransac = match context.ransac_downsampling_cell_size {
+            Some(cell_size) => ransac.with_downsampling(*cell_size),
+            None => ransac,
+        };
         let mut lines_in_ground = Vec::new();
         for _ in 0..*context.maximum_number_of_lines {
             if ransac.unused_points.len() < *context.minimum_number_of_points_on_line {
@@ -105,13 +147,23 @@ impl LineDetection {
             let RansacResult {
                 line: ransac_line,
                 used_points,
-            } = ransac.next_line(
-                *context.ransac_iterations,
-                *context.maximum_fit_distance_in_ground,
-                *context.maximum_fit_distance_in_ground + *context.margin_for_point_inclusion,
-            );
-            let ransac_line =
-                ransac_line.expect("Insufficient number of line points. Cannot fit line.");
+                ..
+            } = match context.ransac_time_budget {
+                Some(time_budget) => ransac.next_line_with_time_budget(
+                    *context.ransac_iterations,
+                    *context.maximum_fit_distance_in_ground,
+                    *context.maximum_fit_distance_in_ground + *context.margin_for_point_inclusion,
+                    *time_budget,
+                ),
+                None => ransac.next_line(
+                    *context.ransac_iterations,
+                    *context.maximum_fit_distance_in_ground,
+                    *context.maximum_fit_distance_in_ground + *context.margin_for_point_inclusion,
+                ),
+            };
+            let Some(ransac_line) = ransac_line else {
+                break;
+            };
             if used_points.len() < *context.minimum_number_of_points_on_line {
                 discarded_lines.push((ransac_line, LineDiscardReason::TooFewPoints));
                 break;
@@ -179,6 +231,20 @@ impl LineDetection {
                 image_lines.push(Line(start_point_in_ground, end_point_in_ground));
             }
         }
+        // Without a pose, the robot is assumed to stand still since the last cycle.
+        let last_ground_to_current_ground =
+            match (context.ground_to_field, self.last_ground_to_field) {
+                (Some(ground_to_field), Some(last_ground_to_field)) => {
+                    ground_to_field.inverse() * last_ground_to_field
+                }
+                _ => Isometry2::identity(),
+            };
+        self.last_ground_to_field = context.ground_to_field.copied();
+        self.line_tracker.update(
+            &lines_in_ground,
+            last_ground_to_current_ground,
+            context.line_tracking,
+        );
         let line_data = LineData {
             lines: lines_in_ground,
             used_segments,
@@ -212,6 +278,7 @@ impl LineDetection {
 
         Ok(MainOutputs {
             line_data: Some(line_data).into(),
+            tracked_lines: self.line_tracker.lines().into(),
         })
     }
 }
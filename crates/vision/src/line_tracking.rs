@@ -0,0 +1,219 @@
+use coordinate_systems::Ground;
+use geometry::line::{Line, Line2};
+use linear_algebra::Isometry2;
+use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
+use types::parameters::LineTrackingParameters;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct LineTrack {
+    line: Line2<Ground>,
+    missed_cycles: usize,
+}
+
+/// Associates the lines fitted in each cycle with the lines of previous cycles and low-pass
+/// filters their end points, so that tracked lines do not jitter from cycle to cycle.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct LineTracker {
+    tracks: Vec<LineTrack>,
+}
+
+impl LineTracker {
+    /// Updates the tracks with the lines fitted in this cycle. The tracks are first moved by
+    /// `last_ground_to_current_ground`, i.e. the robot's motion since the last update. Each line
+    /// then updates the closest matching track not yet updated in this cycle, or starts a new
+    /// track. Tracks missing for more than `maximum_missed_cycles` cycles are dropped.
+    pub fn update(
+        &mut self,
+        lines: &[Line2<Ground>],
+        last_ground_to_current_ground: Isometry2<Ground, Ground>,
+        parameters: &LineTrackingParameters,
+    ) {
+        for track in &mut self.tracks {
+            track.line = last_ground_to_current_ground * Line(track.line.0, track.line.1);
+        }
+        let mut updated = vec![false; self.tracks.len()];
+        for line in lines {
+            let line = Line(line.0, line.1).canonicalize();
+            let closest_match = self
+                .tracks
+                .iter()
+                .enumerate()
+                .filter(|(index, track)| {
+                    !updated[*index]
+                        && track.line.is_approximately(
+                            &line,
+                            parameters.maximum_angle_difference,
+                            parameters.maximum_distance,
+                        )
+                })
+                .min_by_key(|(_, track)| {
+                    NotNan::new(track.line.distance_to_point(line.center()))
+                        .expect("line distance should not be NaN")
+                })
+                .map(|(index, _)| index);
+            match closest_match {
+                Some(index) => {
+                    let track = &mut self.tracks[index];
+                    track.line = smoothed(&track.line, line, parameters.smoothing_factor);
+                    track.missed_cycles = 0;
+                    updated[index] = true;
+                }
+                None => {
+                    self.tracks.push(LineTrack {
+                        line,
+                        missed_cycles: 0,
+                    });
+                    updated.push(true);
+                }
+            }
+        }
+
+        for (track, updated) in self.tracks.iter_mut().zip(updated) {
+            if !updated {
+                track.missed_cycles += 1;
+            }
+        }
+        self.tracks
+            .retain(|track| track.missed_cycles <= parameters.maximum_missed_cycles);
+    }
+
+    pub fn lines(&self) -> Vec<Line2<Ground>> {
+        self.tracks
+            .iter()
+            .map(|track| Line(track.line.0, track.line.1))
+            .collect()
+    }
+}
+
+/// Moves the end points of `track` towards the ones of `measurement` by `smoothing_factor`,
+/// where 1.0 replaces the tracked line.
+fn smoothed(
+    track: &Line2<Ground>,
+    measurement: Line2<Ground>,
+    smoothing_factor: f32,
+) -> Line2<Ground> {
+    // Canonicalization may flip nearly vertical lines, align them with the track first.
+    let measurement = if (track.1 - track.0).dot(measurement.1 - measurement.0) < 0.0 {
+        Line(measurement.1, measurement.0)
+    } else {
+        measurement
+    };
+    Line(
+        track.0 + (measurement.0 - track.0) * smoothing_factor,
+        track.1 + (measurement.1 - track.1) * smoothing_factor,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use linear_algebra::{point, vector};
+
+    use super::*;
+
+    const PARAMETERS: LineTrackingParameters = LineTrackingParameters {
+        maximum_angle_difference: 0.1,
+        maximum_distance: 0.2,
+        smoothing_factor: 0.5,
+        maximum_missed_cycles: 1,
+    };
+
+    #[test]
+    fn jittering_line_is_smoothed() {
+        let mut tracker = LineTracker::default();
+
+        tracker.update(
+            &[Line(point![0.0, 0.0], point![2.0, 0.0])],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        tracker.update(
+            &[Line(point![2.0, 0.1], point![0.0, 0.1])],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        tracker.update(
+            &[Line(point![0.0, -0.1], point![2.0, -0.1])],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+
+        let lines = tracker.lines();
+        assert_eq!(lines.len(), 1);
+        assert_relative_eq!(lines[0].0, point![0.0, -0.025]);
+        assert_relative_eq!(lines[0].1, point![2.0, -0.025]);
+    }
+
+    #[test]
+    fn tracks_move_with_the_robot() {
+        let mut tracker = LineTracker::default();
+        let walked_forward = Isometry2::from_parts(vector![-0.5, 0.0], 0.0);
+
+        tracker.update(
+            &[Line(point![2.0, -1.0], point![2.0, 1.0])],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        tracker.update(
+            &[Line(point![1.5, -1.0], point![1.5, 1.0])],
+            walked_forward,
+            &PARAMETERS,
+        );
+
+        let lines = tracker.lines();
+        assert_eq!(lines.len(), 1);
+        assert_relative_eq!(lines[0], Line(point![1.5, -1.0], point![1.5, 1.0]));
+    }
+
+    #[test]
+    fn unmatched_lines_start_new_tracks() {
+        let mut tracker = LineTracker::default();
+
+        tracker.update(
+            &[Line(point![0.0, 0.0], point![2.0, 0.0])],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        tracker.update(
+            &[
+                Line(point![0.0, 0.0], point![2.0, 0.0]),
+                Line(point![0.0, 1.0], point![2.0, 1.0]),
+                Line(point![0.0, 0.0], point![0.0, 2.0]),
+            ],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+
+        assert_eq!(tracker.lines().len(), 3);
+    }
+
+    #[test]
+    fn stale_tracks_expire() {
+        let mut tracker = LineTracker::default();
+        let first = Line(point![0.0, 0.0], point![2.0, 0.0]);
+        let second = Line(point![0.0, 1.0], point![2.0, 1.0]);
+
+        tracker.update(
+            &[Line(first.0, first.1), Line(second.0, second.1)],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        tracker.update(
+            &[Line(second.0, second.1)],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        assert_eq!(tracker.lines().len(), 2);
+
+        tracker.update(
+            &[Line(second.0, second.1)],
+            Isometry2::identity(),
+            &PARAMETERS,
+        );
+        let lines = tracker.lines();
+        assert_eq!(lines.len(), 1);
+        assert_relative_eq!(lines[0].0, second.0);
+        assert_relative_eq!(lines[0].1, second.1);
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
 use geometry::line::{Line, Line2};
 use linear_algebra::Point2;
 use ordered_float::NotNan;
@@ -7,20 +12,133 @@ use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, SeedableRng};
 pub struct RansacResult<Frame> {
     pub line: Option<Line2<Frame>>,
     pub used_points: Vec<Point2<Frame>>,
+    /// Points within the score distance but outside the inclusion distance of the line. They are
+    /// not used and stay available for following lines.
+    pub borderline_points: Vec<Point2<Frame>>,
+    pub mean_inlier_distance: f32,
+    pub max_inlier_distance: f32,
+}
+
+impl<Frame> RansacResult<Frame> {
+    fn no_line() -> Self {
+        Self {
+            line: None,
+            used_points: vec![],
+            borderline_points: vec![],
+            mean_inlier_distance: 0.0,
+            max_inlier_distance: 0.0,
+        }
+    }
+}
+
+/// Merges results whose lines lie on approximately the same infinite line, e.g. fragments of a
+/// field line split by the center circle or an occlusion. Merged results use the points of all
+/// fragments and their line is refitted to span them. Results without a line are dropped.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn merge_collinear<Frame>(
+    lines: Vec<RansacResult<Frame>>,
+    angle_tolerance: f32,
+    distance_tolerance: f32,
+) -> Vec<RansacResult<Frame>> {
+    let mut merged_results: Vec<RansacResult<Frame>> = Vec::new();
+    for mut result in lines {
+        let Some(mut line) = result.line else {
+            continue;
+        };
+        // A merge moves the line, which may bring further results within the tolerances.
+        while let Some(index) = merged_results.iter().position(|merged| {
+            merged.line.as_ref().is_some_and(|merged_line| {
+                merged_line.is_approximately(&line, angle_tolerance, distance_tolerance)
+            })
+        }) {
+            let merged = merged_results.swap_remove(index);
+            result.used_points.extend(merged.used_points);
+            result.borderline_points.extend(merged.borderline_points);
+            line = Line2::from_points_least_squares(&result.used_points, None)
+                .and_then(|fitted_line| fitted_line.segment_spanning(&result.used_points))
+                .unwrap_or(line);
+            let inlier_distances: Vec<_> = result
+                .used_points
+                .iter()
+                .map(|point| line.distance_to_point(*point))
+                .collect();
+            (result.mean_inlier_distance, result.max_inlier_distance) =
+                distance_statistics(&inlier_distances);
+        }
+        result.line = Some(line);
+        merged_results.push(result);
+    }
+    merged_results
+}
+
+/// Result of [`Ransac::next_parallel_pair`], with one result per line. Both lines are `None` if
+/// no pair was found.
+#[derive(Default, Debug, PartialEq)]
+pub struct ParallelPairResult<Frame> {
+    pub first: RansacResult<Frame>,
+    pub second: RansacResult<Frame>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct AngleConstraint {
+    pub expected_angle: f32,
+    pub tolerance: f32,
 }
 
+const ITERATIONS_BETWEEN_TIME_CHECKS: usize = 8;
+const MAXIMUM_SAMPLING_ATTEMPTS: usize = 8;
+
 pub struct Ransac<Frame> {
     pub unused_points: Vec<Point2<Frame>>,
+    original_number_of_points: usize,
     random_number_generator: StdRng,
+    downsampling_cell_size: Option<f32>,
 }
 
 impl<Frame> Ransac<Frame> {
     pub fn new(unused_points: Vec<Point2<Frame>>) -> Self {
         Self {
+            original_number_of_points: unused_points.len(),
             unused_points,
             random_number_generator: StdRng::from_rng(thread_rng())
                 .expect("Failed to create random number generator"),
+            downsampling_cell_size: None,
+        }
+    }
+
+    pub fn new_seeded(unused_points: Vec<Point2<Frame>>, seed: u64) -> Self {
+        Self {
+            original_number_of_points: unused_points.len(),
+            unused_points,
+            random_number_generator: StdRng::seed_from_u64(seed),
+            downsampling_cell_size: None,
+        }
+    }
+
+    /// Samples and scores line hypotheses on a grid-downsampled copy of the unused points with
+    /// one point per cell of `cell_size`, which speeds up fitting on dense point sets. Points are
+    /// still included in the resulting lines at full resolution. Applies to `next_line` and its
+    /// variants, but not to `next_parallel_pair`.
+    pub fn with_downsampling(mut self, cell_size: f32) -> Self {
+        self.downsampling_cell_size = Some(cell_size);
+        self
+    }
+
+    /// Number of points not yet used by any line.
+    pub fn remaining(&self) -> usize {
+        self.unused_points.len()
+    }
+
+    /// Fraction of the points passed on construction that has been used by lines so far. Without
+    /// any points, there is nothing left to explain and the ratio is 1.0.
+    pub fn consumed_ratio(&self) -> f32 {
+        if self.original_number_of_points == 0 {
+            return 1.0;
         }
+        let consumed = self
+            .original_number_of_points
+            .saturating_sub(self.unused_points.len());
+        consumed as f32 / self.original_number_of_points as f32
     }
 }
 
@@ -31,77 +149,456 @@ impl<Frame> Ransac<Frame> {
         maximum_score_distance: f32,
         maximum_inclusion_distance: f32,
     ) -> RansacResult<Frame> {
-        if self.unused_points.len() < 2 {
-            return RansacResult {
-                line: None,
-                used_points: vec![],
-            };
+        self.fit_next_line(
+            iterations,
+            maximum_score_distance,
+            maximum_inclusion_distance,
+            None,
+            None,
+            None,
+            false,
+        )
+    }
+
+    /// Like `next_line`, but discards hypotheses whose direction deviates by more than the
+    /// constraint's tolerance from its expected angle before scoring them. Lines are undirected,
+    /// so the expected angle and its opposite are equivalent. Returns no line if every hypothesis
+    /// was discarded.
+    pub fn next_line_with_angle_constraint(
+        &mut self,
+        iterations: usize,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+        angle_constraint: AngleConstraint,
+    ) -> RansacResult<Frame> {
+        self.fit_next_line(
+            iterations,
+            maximum_score_distance,
+            maximum_inclusion_distance,
+            None,
+            None,
+            Some(angle_constraint),
+            false,
+        )
+    }
+
+    /// Like `next_line`, but scores and includes points by their distance to a segment instead of
+    /// the infinite line. Starting at one of the sampled points, the segment is repeatedly extended
+    /// to span all points within the score distance of it until no more points are added. Distant
+    /// collinear points therefore do not count for short line segments. The returned line spans
+    /// the found segment.
+    pub fn next_line_segment(
+        &mut self,
+        iterations: usize,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+    ) -> RansacResult<Frame> {
+        self.fit_next_line(
+            iterations,
+            maximum_score_distance,
+            maximum_inclusion_distance,
+            None,
+            None,
+            None,
+            true,
+        )
+    }
+
+    /// Like `next_line`, but stops generating hypotheses once `time_budget` is spent and returns
+    /// the best line found so far. The clock is only read every few iterations.
+    pub fn next_line_with_time_budget(
+        &mut self,
+        iterations: usize,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+        time_budget: Duration,
+    ) -> RansacResult<Frame> {
+        self.fit_next_line(
+            iterations,
+            maximum_score_distance,
+            maximum_inclusion_distance,
+            None,
+            Some(Instant::now() + time_budget),
+            None,
+            false,
+        )
+    }
+
+    /// Like `next_line`, but only considers lines through `anchor`, e.g. a detected corner. Each
+    /// hypothesis samples a single point and the line through it and the anchor is scored. The
+    /// anchor itself does not need to be one of the unused points.
+    pub fn next_line_through(
+        &mut self,
+        anchor: Point2<Frame>,
+        iterations: usize,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+    ) -> RansacResult<Frame> {
+        self.fit_next_line(
+            iterations,
+            maximum_score_distance,
+            maximum_inclusion_distance,
+            Some(anchor),
+            None,
+            None,
+            false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn fit_next_line(
+        &mut self,
+        iterations: usize,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+        anchor: Option<Point2<Frame>>,
+        deadline: Option<Instant>,
+        angle_constraint: Option<AngleConstraint>,
+        score_segments: bool,
+    ) -> RansacResult<Frame> {
+        let minimum_number_of_points = if anchor.is_some() { 1 } else { 2 };
+        if self.unused_points.len() < minimum_number_of_points {
+            return RansacResult::no_line();
         }
         let maximum_score_distance_squared = maximum_score_distance * maximum_score_distance;
         let maximum_inclusion_distance_squared =
             maximum_inclusion_distance * maximum_inclusion_distance;
+        let squared_distance = |line: &Line2<Frame>, point: Point2<Frame>| {
+            if score_segments {
+                line.squared_distance_to_segment(point)
+            } else {
+                line.squared_distance_to_point(point)
+            }
+        };
+        let downsampled_points;
+        let scored_points = match self.downsampling_cell_size {
+            Some(cell_size) => {
+                downsampled_points = downsample(&self.unused_points, cell_size);
+                &downsampled_points
+            }
+            None => &self.unused_points,
+        };
+        if scored_points.len() < minimum_number_of_points {
+            return RansacResult::no_line();
+        }
+        let point_columns = (!score_segments).then(|| PointColumns::new(scored_points));
         let best_line = (0..iterations)
-            .map(|_| {
-                let mut points = self
+            .take_while(|iteration| {
+                let should_check_time =
+                    *iteration > 0 && iteration % ITERATIONS_BETWEEN_TIME_CHECKS == 0;
+                match deadline {
+                    Some(deadline) if should_check_time => Instant::now() < deadline,
+                    _ => true,
+                }
+            })
+            .filter_map(|_| {
+                let line = match anchor {
+                    Some(anchor) => sample_line_through(
+                        anchor,
+                        scored_points,
+                        &mut self.random_number_generator,
+                    )?,
+                    None => sample_distinct_line(scored_points, &mut self.random_number_generator)?,
+                };
+                if let Some(constraint) = angle_constraint {
+                    if line.acute_angle_to_direction(constraint.expected_angle)
+                        > constraint.tolerance
+                    {
+                        return None;
+                    }
+                }
+                if let Some(point_columns) = &point_columns {
+                    let score = point_columns.line_score(&line, maximum_score_distance);
+                    return Some((line, score));
+                }
+                let line = grow_segment(&line, scored_points, maximum_score_distance_squared);
+                let score: f32 = scored_points
+                    .iter()
+                    .map(|point| line.squared_distance_to_segment(*point))
+                    .filter(|squared_distance| *squared_distance <= maximum_score_distance_squared)
+                    .map(|squared_distance| 1.0 - squared_distance.sqrt() / maximum_score_distance)
+                    .sum();
+                Some((line, score))
+            })
+            .max_by_key(|(_line, score)| NotNan::new(*score).expect("score should never be NaN"));
+        let Some((best_line, _score)) = best_line else {
+            return RansacResult::no_line();
+        };
+        let mut used_points = Vec::new();
+        let mut unused_points = Vec::new();
+        let mut borderline_points = Vec::new();
+        let mut inlier_distances = Vec::new();
+        for point in self.unused_points.drain(..) {
+            let squared_distance = squared_distance(&best_line, point);
+            if squared_distance <= maximum_inclusion_distance_squared {
+                used_points.push(point);
+                inlier_distances.push(squared_distance.sqrt());
+            } else {
+                if squared_distance <= maximum_score_distance_squared {
+                    borderline_points.push(point);
+                }
+                unused_points.push(point);
+            }
+        }
+        self.unused_points = unused_points;
+        let (mean_inlier_distance, max_inlier_distance) = distance_statistics(&inlier_distances);
+        RansacResult {
+            line: Some(best_line),
+            used_points,
+            borderline_points,
+            mean_inlier_distance,
+            max_inlier_distance,
+        }
+    }
+
+    /// Fits two parallel lines at once, e.g. both boundaries of the penalty area. Each hypothesis
+    /// is a line through a random point pair and its parallel through a third point at roughly
+    /// `separation_hint` distance. Hypotheses deviating from the hint by more than half of it are
+    /// discarded. Every point only counts for the closer of both lines, so close lines do not
+    /// compete for the same points as with consecutive fits.
+    pub fn next_parallel_pair(
+        &mut self,
+        iterations: usize,
+        separation_hint: f32,
+        maximum_score_distance: f32,
+        maximum_inclusion_distance: f32,
+    ) -> ParallelPairResult<Frame> {
+        let no_pair = || ParallelPairResult {
+            first: RansacResult::no_line(),
+            second: RansacResult::no_line(),
+        };
+        if self.unused_points.len() < 3 {
+            return no_pair();
+        }
+        let separation_range = 0.5 * separation_hint..=1.5 * separation_hint;
+        let best_pair = (0..iterations)
+            .filter_map(|_| {
+                let first =
+                    sample_distinct_line(&self.unused_points, &mut self.random_number_generator)?;
+                let candidates: Vec<_> = self
                     .unused_points
-                    .choose_multiple(&mut self.random_number_generator, 2);
-                let line = Line(*points.next().unwrap(), *points.next().unwrap());
+                    .iter()
+                    .filter(|point| separation_range.contains(&first.distance_to_point(**point)))
+                    .collect();
+                let anchor = **candidates.choose(&mut self.random_number_generator)?;
+                let second = Line(anchor, anchor + (first.1 - first.0));
                 let score: f32 = self
                     .unused_points
                     .iter()
-                    .filter(|&point| {
-                        line.squared_distance_to_point(*point) <= maximum_score_distance_squared
+                    .map(|point| {
+                        first
+                            .distance_to_point(*point)
+                            .min(second.distance_to_point(*point))
                     })
-                    .map(|point| 1.0 - line.distance_to_point(*point) / maximum_score_distance)
+                    .filter(|distance| *distance <= maximum_score_distance)
+                    .map(|distance| 1.0 - distance / maximum_score_distance)
                     .sum();
-                (line, score)
+                Some((first, second, score))
             })
-            .max_by_key(|(_line, score)| NotNan::new(*score).expect("score should never be NaN"))
-            .expect("max_by_key erroneously returned no result")
-            .0;
-        let (used_points, unused_points) = self.unused_points.iter().partition(|point| {
-            best_line.squared_distance_to_point(**point) <= maximum_inclusion_distance_squared
-        });
+            .max_by_key(|(_first, _second, score)| {
+                NotNan::new(*score).expect("score should never be NaN")
+            });
+        let Some((first_line, second_line, _score)) = best_pair else {
+            return no_pair();
+        };
+
+        let mut result = ParallelPairResult {
+            first: RansacResult {
+                line: Some(Line(first_line.0, first_line.1)),
+                ..RansacResult::no_line()
+            },
+            second: RansacResult {
+                line: Some(Line(second_line.0, second_line.1)),
+                ..RansacResult::no_line()
+            },
+        };
+        let mut first_inlier_distances = Vec::new();
+        let mut second_inlier_distances = Vec::new();
+        let mut unused_points = Vec::new();
+        for point in self.unused_points.drain(..) {
+            let first_distance = first_line.distance_to_point(point);
+            let second_distance = second_line.distance_to_point(point);
+            let (line_result, inlier_distances, distance) = if first_distance <= second_distance {
+                (
+                    &mut result.first,
+                    &mut first_inlier_distances,
+                    first_distance,
+                )
+            } else {
+                (
+                    &mut result.second,
+                    &mut second_inlier_distances,
+                    second_distance,
+                )
+            };
+            if distance <= maximum_inclusion_distance {
+                line_result.used_points.push(point);
+                inlier_distances.push(distance);
+            } else {
+                if distance <= maximum_score_distance {
+                    line_result.borderline_points.push(point);
+                }
+                unused_points.push(point);
+            }
+        }
         self.unused_points = unused_points;
-        RansacResult {
-            line: Some(best_line),
-            used_points,
+        (
+            result.first.mean_inlier_distance,
+            result.first.max_inlier_distance,
+        ) = distance_statistics(&first_inlier_distances);
+        (
+            result.second.mean_inlier_distance,
+            result.second.max_inlier_distance,
+        ) = distance_statistics(&second_inlier_distances);
+        result
+    }
+}
+
+/// Draws two points without replacement and retries a bounded number of times while they
+/// coincide, since a line through identical points has no direction. Returns `None` if every
+/// attempt produced coinciding points, e.g. because all remaining points are duplicates.
+fn sample_distinct_line<Frame>(
+    points: &[Point2<Frame>],
+    random_number_generator: &mut StdRng,
+) -> Option<Line2<Frame>> {
+    (0..MAXIMUM_SAMPLING_ATTEMPTS).find_map(|_| {
+        let mut sample = points.choose_multiple(random_number_generator, 2);
+        let line = Line(*sample.next().unwrap(), *sample.next().unwrap());
+        (line.0 != line.1).then_some(line)
+    })
+}
+
+/// Draws a single point and retries a bounded number of times while it coincides with `anchor`.
+fn sample_line_through<Frame>(
+    anchor: Point2<Frame>,
+    points: &[Point2<Frame>],
+    random_number_generator: &mut StdRng,
+) -> Option<Line2<Frame>> {
+    (0..MAXIMUM_SAMPLING_ATTEMPTS).find_map(|_| {
+        let point = *points.choose(random_number_generator)?;
+        (point != anchor).then_some(Line(anchor, point))
+    })
+}
+
+/// Grows a segment on `line`, starting at its first point, to span all points within the given
+/// distance of the segment. Every extension may bring further points within reach, so this is
+/// repeated until no more points are added.
+fn grow_segment<Frame>(
+    line: &Line2<Frame>,
+    points: &[Point2<Frame>],
+    maximum_distance_squared: f32,
+) -> Line2<Frame> {
+    let mut segment = Line(line.0, line.0);
+    let mut number_of_points_near_segment = 0;
+    loop {
+        let points_near_segment: Vec<_> = points
+            .iter()
+            .copied()
+            .filter(|point| segment.squared_distance_to_segment(*point) <= maximum_distance_squared)
+            .collect();
+        if points_near_segment.len() == number_of_points_near_segment {
+            return segment;
         }
+        number_of_points_near_segment = points_near_segment.len();
+        segment = line
+            .segment_spanning(&points_near_segment)
+            .expect("the start point is always near the segment");
     }
 }
 
+/// Keeps the first point of every grid cell of `cell_size`, preserving the order of the points.
+fn downsample<Frame>(points: &[Point2<Frame>], cell_size: f32) -> Vec<Point2<Frame>> {
+    let mut occupied_cells = HashSet::new();
+    points
+        .iter()
+        .filter(|point| {
+            let cell = (
+                (point.x() / cell_size).floor() as i32,
+                (point.y() / cell_size).floor() as i32,
+            );
+            occupied_cells.insert(cell)
+        })
+        .copied()
+        .collect()
+}
+
+/// Point coordinates stored column-wise, so scoring a line hypothesis is a branchless loop over
+/// contiguous `f32`s which the compiler vectorizes.
+struct PointColumns {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+}
+
+impl PointColumns {
+    fn new<Frame>(points: &[Point2<Frame>]) -> Self {
+        Self {
+            xs: points.iter().map(|point| point.x()).collect(),
+            ys: points.iter().map(|point| point.y()).collect(),
+        }
+    }
+
+    /// Sum of `1 - distance / maximum_score_distance` over all points closer than
+    /// `maximum_score_distance` to the infinite line. Distances are taken along the unit normal,
+    /// which avoids the per-point division of [`Line::squared_distance_to_point`].
+    fn line_score<Frame>(&self, line: &Line2<Frame>, maximum_score_distance: f32) -> f32 {
+        let direction = line.1 - line.0;
+        let length = direction.norm();
+        let normal_x = -direction.y() / length;
+        let normal_y = direction.x() / length;
+        let offset = normal_x * line.0.x() + normal_y * line.0.y();
+        let inverse_maximum_score_distance = 1.0 / maximum_score_distance;
+        self.xs
+            .iter()
+            .zip(&self.ys)
+            .map(|(x, y)| {
+                let distance = (normal_x * x + normal_y * y - offset).abs();
+                (1.0 - distance * inverse_maximum_score_distance).max(0.0)
+            })
+            .sum()
+    }
+}
+
+fn distance_statistics(distances: &[f32]) -> (f32, f32) {
+    if distances.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = distances.iter().sum::<f32>() / distances.len() as f32;
+    let max = distances.iter().copied().fold(0.0, f32::max);
+    (mean, max)
+}
+
 #[cfg(test)]
 mod test {
+    use std::{
+        f32::consts::{FRAC_PI_4, PI},
+        ops::Range,
+    };
+
     use approx::assert_relative_eq;
     use linear_algebra::point;
+    use rand::Rng;
 
     use super::*;
 
     #[derive(Debug, PartialEq, Eq, Default)]
     struct SomeFrame;
 
-    fn ransac_with_seed(unused_points: Vec<Point2<SomeFrame>>, seed: u64) -> Ransac<SomeFrame> {
-        Ransac {
-            unused_points,
-            random_number_generator: StdRng::seed_from_u64(seed),
-        }
-    }
-
     #[test]
     fn ransac_empty_input() {
-        let mut ransac = ransac_with_seed(vec![], 0);
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(vec![], 0);
         assert_eq!(ransac.next_line(10, 5.0, 5.0), RansacResult::default());
     }
 
     #[test]
     fn ransac_single_point() {
-        let mut ransac = ransac_with_seed(vec![point![15.0, 15.0]], 0);
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(vec![point![15.0, 15.0]], 0);
         assert_eq!(ransac.next_line(10, 5.0, 5.0), RansacResult::default());
     }
 
     #[test]
     fn ransac_two_points() {
-        let mut ransac = ransac_with_seed(vec![point![15.0, 15.0], point![30.0, 30.0]], 0);
+        let mut ransac =
+            Ransac::<SomeFrame>::new_seeded(vec![point![15.0, 15.0], point![30.0, 30.0]], 0);
         let result = ransac.next_line(10, 5.0, 5.0);
         assert_relative_eq!(
             result.line.expect("No line found"),
@@ -119,11 +616,354 @@ mod test {
             .map(|x| point![x as f32, y_intercept + x as f32 * slope])
             .collect();
 
-        let mut ransac = ransac_with_seed(points.clone(), 0);
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points.clone(), 0);
         let result = ransac.next_line(15, 1.0, 1.0);
         let line = result.line.expect("No line was found");
         assert_relative_eq!(line.slope(), slope, epsilon = 0.0001);
         assert_relative_eq!(line.y_axis_intercept(), y_intercept, epsilon = 0.0001);
         assert_eq!(result.used_points, points);
     }
+
+    #[test]
+    fn ransac_tracks_consumed_points() {
+        let mut points: Vec<Point2<SomeFrame>> = (0..8).map(|x| point![x as f32, 0.0]).collect();
+        points.extend([point![3.0, 5.0], point![7.0, -4.0]]);
+        let mut ransac = Ransac::new_seeded(points, 0);
+        assert_eq!(ransac.remaining(), 10);
+        assert_eq!(ransac.consumed_ratio(), 0.0);
+
+        ransac.next_line(10, 0.5, 0.5);
+        assert_eq!(ransac.remaining(), 2);
+        assert_relative_eq!(ransac.consumed_ratio(), 0.8);
+
+        let ransac = Ransac::<SomeFrame>::new_seeded(vec![], 0);
+        assert_eq!(ransac.remaining(), 0);
+        assert_eq!(ransac.consumed_ratio(), 1.0);
+    }
+
+    #[test]
+    fn ransac_reports_inlier_distances() {
+        let points = vec![
+            point![0.0, 0.0],
+            point![10.0, 0.0],
+            point![20.0, 0.0],
+            point![5.0, 0.5],
+            point![15.0, -1.0],
+            point![7.0, 20.0],
+        ];
+
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points, 0);
+        let result = ransac.next_line(50, 1.5, 1.5);
+
+        let line = result.line.expect("No line found");
+        assert_relative_eq!(line.distance_to_point(point![0.0, 0.0]), 0.0);
+        assert_relative_eq!(line.distance_to_point(point![20.0, 0.0]), 0.0);
+        assert_eq!(result.used_points.len(), 5);
+        assert_relative_eq!(result.mean_inlier_distance, 0.3);
+        assert_relative_eq!(result.max_inlier_distance, 1.0);
+    }
+
+    #[test]
+    fn ransac_reports_borderline_points_and_keeps_them_unused() {
+        let points = vec![
+            point![0.0, 0.0],
+            point![10.0, 0.0],
+            point![20.0, 0.0],
+            point![30.0, 0.0],
+            point![5.0, 1.5],
+            point![15.0, 30.0],
+        ];
+
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points, 0);
+        let result = ransac.next_line(50, 2.0, 1.0);
+
+        assert_eq!(result.used_points.len(), 4);
+        assert_eq!(result.borderline_points, vec![point![5.0, 1.5]]);
+        assert_eq!(
+            ransac.unused_points,
+            vec![point![5.0, 1.5], point![15.0, 30.0]]
+        );
+    }
+
+    #[test]
+    fn ransac_angle_constraint_prefers_weaker_line_within_tolerance() {
+        let diagonal_points = (0..40).map(|x| point![x as f32, x as f32]);
+        let horizontal_points = (0..15).map(|x| point![x as f32 * 3.0, 50.0]);
+        let points: Vec<Point2<SomeFrame>> = diagonal_points.chain(horizontal_points).collect();
+
+        let unconstrained_line = Ransac::new_seeded(points.clone(), 0)
+            .next_line(100, 0.5, 0.5)
+            .line
+            .expect("No line found");
+        assert_relative_eq!(
+            unconstrained_line.acute_angle_to_direction(FRAC_PI_4),
+            0.0,
+            epsilon = 1e-6
+        );
+
+        let mut ransac = Ransac::new_seeded(points, 0);
+        let result = ransac.next_line_with_angle_constraint(
+            100,
+            0.5,
+            0.5,
+            AngleConstraint {
+                expected_angle: PI,
+                tolerance: 0.1,
+            },
+        );
+        let line = result.line.expect("No line found");
+        assert_relative_eq!(line.acute_angle_to_direction(0.0), 0.0, epsilon = 1e-6);
+        assert_eq!(result.used_points.len(), 15);
+    }
+
+    #[test]
+    fn ransac_angle_constraint_without_matching_hypothesis_returns_no_line() {
+        let points: Vec<Point2<SomeFrame>> = (0..10).map(|y| point![0.0, y as f32]).collect();
+
+        let mut ransac = Ransac::new_seeded(points, 0);
+        let result = ransac.next_line_with_angle_constraint(
+            20,
+            0.5,
+            0.5,
+            AngleConstraint {
+                expected_angle: 0.0,
+                tolerance: 0.1,
+            },
+        );
+        assert_eq!(result.line, None);
+        assert_eq!(ransac.unused_points.len(), 10);
+    }
+
+    #[test]
+    fn ransac_time_budget_stops_early_with_best_line() {
+        let slope = 5.3;
+        let y_intercept = -83.1;
+        let points: Vec<_> = (0..100)
+            .map(|x| point![x as f32, y_intercept + x as f32 * slope])
+            .collect();
+
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points.clone(), 0);
+        let start = Instant::now();
+        let result =
+            ransac.next_line_with_time_budget(usize::MAX, 1.0, 1.0, Duration::from_millis(10));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let line = result.line.expect("No line was found");
+        assert_relative_eq!(line.slope(), slope, epsilon = 0.0001);
+        assert_relative_eq!(line.y_axis_intercept(), y_intercept, epsilon = 0.0001);
+        assert_eq!(result.used_points, points);
+    }
+
+    #[test]
+    fn ransac_zero_time_budget_still_evaluates_hypotheses() {
+        let mut ransac =
+            Ransac::<SomeFrame>::new_seeded(vec![point![15.0, 15.0], point![30.0, 30.0]], 0);
+        let result = ransac.next_line_with_time_budget(10, 5.0, 5.0, Duration::ZERO);
+        assert_eq!(
+            result.line.expect("No line found"),
+            Line(point![15.0, 15.0], point![30.0, 30.0])
+        );
+    }
+
+    #[test]
+    fn ransac_never_fits_line_through_duplicate_points() {
+        let mut points = vec![point![5.0, 5.0]; 100];
+        points.push(point![0.0, 0.0]);
+        points.push(point![10.0, 0.0]);
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points, 0);
+        let result = ransac.next_line(50, 1.0, 1.0);
+        let line = result.line.expect("No line found");
+        assert_ne!(line.0, line.1);
+    }
+
+    #[test]
+    fn ransac_only_duplicate_points_returns_no_line() {
+        let points = vec![point![5.0, 5.0]; 100];
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(points, 0);
+        assert_eq!(ransac.next_line(10, 1.0, 1.0).line, None);
+        assert_eq!(ransac.unused_points.len(), 100);
+    }
+
+    #[test]
+    fn ransac_parallel_pair_fits_both_rows() {
+        let mut points: Vec<Point2<SomeFrame>> = (0..20)
+            .flat_map(|x| [point![x as f32, 0.0], point![x as f32, 1.0]])
+            .collect();
+        points.push(point![5.0, 10.0]);
+        points.push(point![15.0, -10.0]);
+
+        let mut ransac = Ransac::new_seeded(points, 0);
+        let result = ransac.next_parallel_pair(50, 1.0, 0.3, 0.2);
+
+        let mut rows: Vec<_> = [result.first, result.second]
+            .into_iter()
+            .map(|line_result| {
+                let line = line_result.line.expect("No line found");
+                assert_relative_eq!(line.0.y(), line.1.y());
+                assert_eq!(line_result.used_points.len(), 20);
+                assert!(line_result
+                    .used_points
+                    .iter()
+                    .all(|point| point.y() == line.0.y()));
+                line.0.y()
+            })
+            .collect();
+        rows.sort_by(f32::total_cmp);
+        assert_eq!(rows, [0.0, 1.0]);
+        assert_eq!(ransac.unused_points.len(), 2);
+    }
+
+    #[test]
+    fn ransac_parallel_pair_without_enough_points_returns_no_lines() {
+        let mut ransac =
+            Ransac::<SomeFrame>::new_seeded(vec![point![0.0, 0.0], point![1.0, 0.0]], 0);
+        let result = ransac.next_parallel_pair(10, 1.0, 0.3, 0.2);
+        assert_eq!(result, ParallelPairResult::default());
+    }
+
+    #[test]
+    fn ransac_segment_ignores_distant_collinear_cluster() {
+        let points: Vec<Point2<SomeFrame>> = (0..10)
+            .map(|x| point![x as f32, 0.0])
+            .chain((50..54).map(|x| point![x as f32, 0.0]))
+            .collect();
+
+        let mut ransac = Ransac::new_seeded(points.clone(), 0);
+        let result = ransac.next_line(20, 0.5, 0.5);
+        assert_eq!(result.used_points.len(), 14);
+
+        let mut ransac = Ransac::new_seeded(points, 0);
+        let result = ransac.next_line_segment(20, 1.5, 0.5);
+        let segment = result.line.expect("No line found");
+        assert_relative_eq!(segment.length(), 9.0);
+        assert_eq!(result.used_points.len(), 10);
+        assert!(result.used_points.iter().all(|point| point.x() < 10.0));
+        assert_eq!(ransac.unused_points.len(), 4);
+    }
+
+    #[test]
+    fn ransac_downsampling_into_a_single_cell_returns_no_line() {
+        let points: Vec<Point2<SomeFrame>> =
+            vec![point![0.01, 0.01], point![0.02, 0.03], point![0.04, 0.02]];
+        let mut ransac = Ransac::new_seeded(points, 0).with_downsampling(0.05);
+
+        let result = ransac.next_line(10, 0.05, 0.05);
+
+        assert_eq!(result.line, None);
+        assert_eq!(ransac.remaining(), 3);
+    }
+
+    #[test]
+    fn ransac_downsampling_keeps_detected_line() {
+        let mut random_number_generator = StdRng::seed_from_u64(0);
+        let mut points: Vec<Point2<SomeFrame>> = (0..2000)
+            .map(|index| {
+                let x = index as f32 * 0.002;
+                let noise = random_number_generator.gen_range(-0.01..0.01);
+                point![x, 0.5 * x + 1.0 + noise]
+            })
+            .collect();
+        points.extend((0..200).map(|_| {
+            point![
+                random_number_generator.gen_range(0.0..4.0),
+                random_number_generator.gen_range(-2.0..4.0)
+            ]
+        }));
+
+        let full_resolution = Ransac::new_seeded(points.clone(), 0).next_line(50, 0.05, 0.05);
+        let mut downsampled_ransac = Ransac::new_seeded(points.clone(), 0).with_downsampling(0.05);
+        let downsampled = downsampled_ransac.next_line(50, 0.05, 0.05);
+
+        assert!(downsample(&points, 0.05).len() < points.len() / 4);
+        assert!(downsampled.line.expect("No line found").is_approximately(
+            &full_resolution.line.expect("No line found"),
+            0.02,
+            0.02
+        ));
+        assert!(downsampled.used_points.len() >= 2000);
+        assert_eq!(
+            downsampled.used_points.len() + downsampled_ransac.remaining(),
+            points.len()
+        );
+    }
+
+    #[test]
+    fn ransac_line_through_anchor_passes_through_anchor() {
+        let mut random_number_generator = StdRng::seed_from_u64(0);
+        let anchor = point![0.0, 1.0];
+        let mut points: Vec<Point2<SomeFrame>> = (1..40)
+            .map(|index| {
+                let x = index as f32 * 0.1;
+                let noise = random_number_generator.gen_range(-0.02..0.02);
+                point![x, 2.0 * x + 1.0 + noise]
+            })
+            .collect();
+        points.extend((0..20).map(|_| {
+            point![
+                random_number_generator.gen_range(0.0..4.0),
+                random_number_generator.gen_range(-2.0..4.0)
+            ]
+        }));
+        let mut ransac = Ransac::new_seeded(points, 0);
+
+        let result = ransac.next_line_through(anchor, 20, 0.1, 0.1);
+
+        let line = result.line.expect("No line found");
+        assert_relative_eq!(line.distance_to_point(anchor), 0.0, epsilon = 1e-5);
+        assert_relative_eq!(
+            line.distance_to_point(point![1.0, 3.0]),
+            0.0,
+            epsilon = 0.05
+        );
+        assert!(result.used_points.len() >= 39);
+    }
+
+    #[test]
+    fn ransac_line_through_anchor_needs_a_distinct_point() {
+        let anchor = point![1.0, 1.0];
+        let mut ransac = Ransac::<SomeFrame>::new_seeded(vec![anchor, anchor], 0);
+
+        assert_eq!(
+            ransac.next_line_through(anchor, 10, 0.1, 0.1),
+            RansacResult::no_line()
+        );
+        assert_eq!(ransac.remaining(), 2);
+    }
+
+    fn fragment(x_range: Range<usize>, slope: f32, intercept: f32) -> RansacResult<SomeFrame> {
+        let used_points: Vec<Point2<SomeFrame>> = x_range
+            .map(|x| point![x as f32 * 0.2, slope * x as f32 * 0.2 + intercept])
+            .collect();
+        RansacResult {
+            line: Line2::from_points_least_squares(&used_points, None),
+            used_points,
+            ..RansacResult::no_line()
+        }
+    }
+
+    #[test]
+    fn collinear_fragments_are_merged() {
+        let left = fragment(0..10, 0.5, 1.0);
+        let right = fragment(15..25, 0.5, 1.0);
+
+        let merged = merge_collinear(vec![left, right], 0.05, 0.05);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].used_points.len(), 20);
+        let line = merged[0].line.as_ref().expect("No line found");
+        assert_relative_eq!(line.0, point![0.0, 1.0], epsilon = 1e-4);
+        assert_relative_eq!(line.1, point![4.8, 3.4], epsilon = 1e-4);
+        assert_relative_eq!(merged[0].max_inlier_distance, 0.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn distinct_lines_are_not_merged() {
+        let rising = fragment(0..10, 0.5, 1.0);
+        let parallel = fragment(0..10, 0.5, 2.0);
+        let falling = fragment(0..10, -0.5, 1.0);
+
+        let merged = merge_collinear(vec![rising, parallel, falling], 0.05, 0.05);
+
+        assert_eq!(merged.len(), 3);
+        assert!(merged.iter().all(|result| result.used_points.len() == 10));
+    }
 }
@@ -311,8 +311,11 @@ impl BehaviorCycler {
                     AdditionalOutput::new(true, &mut own_database.additional_outputs.active_action),
                     own_database.main_outputs.expected_referee_position.as_ref(),
                     &true,
+                    &false,
+                    &own_database.main_outputs.sensor_data,
                     &own_database.main_outputs.world_state,
                     &own_database.main_outputs.cycle_time,
+                    &mut cycler_state.stand_up_front_aborted,
                     &parameters.behavior,
                     &parameters.in_walk_kicks,
                     &parameters.field_dimensions,